@@ -0,0 +1,949 @@
+//! Region automation on top of pluggable screen, input and finder backends
+//!
+//! Core only defines the backend traits; the platform crate implements
+//! them for the real desktop, the vision crate provides template matching,
+//! and tests can plug in fakes. An [`AutoRegion`] combines a `Region` with a
+//! set of [`Backends`] and offers the Java-style `find`/`click`/`type` API.
+
+use crate::clock::{Clock, SystemClock};
+use crate::find_failed::{
+    EvidenceWriter, FindFailed, FindFailedAction, FindFailedHandler, FindFailedResponse,
+    FindFailure,
+};
+use crate::{Error, Location, Match, Matches, Pattern, Region, Result, ScreenImage};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long `find` and `exists` keep searching, Java's default
+/// `AutoWaitTimeout`
+pub const DEFAULT_AUTO_WAIT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Searches per second while waiting, Java's default `WaitScanRate`
+pub const DEFAULT_SCAN_RATE: f64 = 3.0;
+
+/// Longest pause between two scans, however low the scan rate
+const MAX_SCAN_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// A mouse button
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+}
+
+/// Source of screen pixels
+pub trait ScreenBackend: Send + Sync {
+    /// Get the capturable area in virtual-desktop coordinates
+    fn bounds(&self) -> Result<Region>;
+
+    /// Capture `region`, clipped to `bounds`
+    ///
+    /// The returned image reports the captured area as its region.
+    fn capture(&self, region: Region) -> Result<ScreenImage>;
+}
+
+/// Sink of mouse and keyboard input
+pub trait InputBackend: Send + Sync {
+    /// Move the pointer to `location`
+    fn move_to(&self, location: Location) -> Result<()>;
+
+    /// Press `button` without releasing it
+    fn button_down(&self, button: MouseButton) -> Result<()>;
+
+    /// Release `button`
+    fn button_up(&self, button: MouseButton) -> Result<()>;
+
+    /// Press and release `button`
+    fn click(&self, button: MouseButton) -> Result<()> {
+        self.button_down(button)?;
+        self.button_up(button)
+    }
+
+    /// Click the left button twice
+    fn double_click(&self) -> Result<()> {
+        self.click(MouseButton::Left)?;
+        self.click(MouseButton::Left)
+    }
+
+    /// Drag with the left button from `from` and drop at `to`
+    fn drag_drop(&self, from: Location, to: Location) -> Result<()> {
+        self.move_to(from)?;
+        self.button_down(MouseButton::Left)?;
+        let moved = self.move_to(to);
+        self.button_up(MouseButton::Left).and(moved)
+    }
+
+    /// Type `text` into the focused window
+    fn type_text(&self, text: &str) -> Result<()>;
+}
+
+/// Pattern search in captured screen images
+pub trait FinderBackend: Send + Sync {
+    /// Find the best match of `pattern` in `image`
+    ///
+    /// Matches are in screen coordinates, i.e. offset by the origin of
+    /// `image.region()`. Returns `Ok(None)` if nothing reaches the pattern's
+    /// similarity.
+    fn find(&self, image: &ScreenImage, pattern: &Pattern) -> Result<Option<Match>>;
+
+    /// Find all matches of `pattern` in `image`, best first
+    fn find_all(&self, image: &ScreenImage, pattern: &Pattern) -> Result<Matches>;
+
+    /// Find the best candidate for `pattern` in `image`, however low it
+    /// scores, to explain why a search failed
+    fn best_candidate(&self, image: &ScreenImage, pattern: &Pattern) -> Result<Option<Match>> {
+        self.find(image, &pattern.clone().similar(0.0))
+    }
+}
+
+/// The backends an `AutoRegion` works with, cheap to clone
+#[derive(Clone)]
+pub struct Backends {
+    screen: Arc<dyn ScreenBackend>,
+    input: Arc<dyn InputBackend>,
+    finder: Arc<dyn FinderBackend>,
+    clock: Arc<dyn Clock>,
+    evidence: Option<Arc<dyn EvidenceWriter>>,
+}
+
+impl Backends {
+    /// Combine a screen, an input and a finder backend
+    pub fn new(
+        screen: impl ScreenBackend + 'static,
+        input: impl InputBackend + 'static,
+        finder: impl FinderBackend + 'static,
+    ) -> Self {
+        Self::from_shared(Arc::new(screen), Arc::new(input), Arc::new(finder))
+    }
+
+    /// Combine backends that are shared with other owners
+    pub fn from_shared(
+        screen: Arc<dyn ScreenBackend>,
+        input: Arc<dyn InputBackend>,
+        finder: Arc<dyn FinderBackend>,
+    ) -> Self {
+        Self {
+            screen,
+            input,
+            finder,
+            clock: Arc::new(SystemClock),
+            evidence: None,
+        }
+    }
+
+    /// Use `clock` instead of the system clock for waiting
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Save evidence of failed searches with `writer`
+    pub fn with_evidence(mut self, writer: Arc<dyn EvidenceWriter>) -> Self {
+        self.evidence = Some(writer);
+        self
+    }
+
+    /// Get the screen backend
+    pub fn screen(&self) -> &dyn ScreenBackend {
+        self.screen.as_ref()
+    }
+
+    /// Get the input backend
+    pub fn input(&self) -> &dyn InputBackend {
+        self.input.as_ref()
+    }
+
+    /// Get the finder backend
+    pub fn finder(&self) -> &dyn FinderBackend {
+        self.finder.as_ref()
+    }
+
+    /// Get the clock used for waiting
+    pub fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
+    }
+
+    /// Automate `region` with these backends
+    pub fn region(&self, region: Region) -> AutoRegion {
+        AutoRegion::new(region, self.clone())
+    }
+
+    /// Automate the whole capturable area of the screen backend
+    pub fn whole_screen(&self) -> Result<AutoRegion> {
+        Ok(self.region(self.screen.bounds()?))
+    }
+}
+
+impl fmt::Debug for Backends {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Backends").finish_non_exhaustive()
+    }
+}
+
+/// A screen region that can be searched and interacted with, like Java's
+/// `Region`
+///
+/// Searches capture the region through the screen backend and hand the
+/// image to the finder backend; mouse and keyboard actions go to the input
+/// backend. Actions on a pattern click or hover its match target (center
+/// plus `Pattern::target_offset`).
+///
+/// `find`, `exists` and the actions built on them keep searching for up to
+/// the auto wait timeout, scanning `scan_rate` times per second, before
+/// they give up. What happens then is up to the region's
+/// [`FindFailedResponse`].
+#[derive(Debug, Clone)]
+pub struct AutoRegion {
+    region: Region,
+    backends: Backends,
+
+    /// How long `find` and `exists` keep searching
+    auto_wait_timeout: Duration,
+
+    /// Searches per second while waiting
+    scan_rate: f64,
+
+    /// What to do when `find` or `wait` gives up
+    find_failed_response: FindFailedResponse,
+
+    /// Decides for the `Prompt` and `Handle` responses
+    find_failed_handler: Option<FindFailedHandler>,
+}
+
+impl AutoRegion {
+    /// Automate `region` with `backends`
+    pub fn new(region: Region, backends: Backends) -> Self {
+        Self {
+            region,
+            backends,
+            auto_wait_timeout: DEFAULT_AUTO_WAIT_TIMEOUT,
+            scan_rate: DEFAULT_SCAN_RATE,
+            find_failed_response: FindFailedResponse::default(),
+            find_failed_handler: None,
+        }
+    }
+
+    /// Set how long `find` and `exists` keep searching (Java's
+    /// `setAutoWaitTimeout`); zero searches only once and `Duration::MAX`
+    /// searches until the pattern appears
+    pub fn with_auto_wait_timeout(mut self, timeout: Duration) -> Self {
+        self.auto_wait_timeout = timeout;
+        self
+    }
+
+    /// Get how long `find` and `exists` keep searching
+    pub fn auto_wait_timeout(&self) -> Duration {
+        self.auto_wait_timeout
+    }
+
+    /// Set the searches per second while waiting (Java's `WaitScanRate`)
+    ///
+    /// Rates below one scan per hour scan once per hour.
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidParameter` if `rate` is not a positive number.
+    pub fn with_scan_rate(mut self, rate: f64) -> Result<Self> {
+        if !(rate > 0.0 && rate.is_finite()) {
+            return Err(Error::InvalidParameter(format!(
+                "Scan rate must be positive, got {}",
+                rate
+            )));
+        }
+        self.scan_rate = rate;
+        Ok(self)
+    }
+
+    /// Get the searches per second while waiting
+    pub fn scan_rate(&self) -> f64 {
+        self.scan_rate
+    }
+
+    /// Set what `find`, `wait` and the actions on patterns do when the
+    /// pattern does not appear (Java's `setFindFailedResponse`)
+    pub fn with_find_failed_response(mut self, response: FindFailedResponse) -> Self {
+        self.find_failed_response = response;
+        self
+    }
+
+    /// Get what happens when a pattern does not appear
+    pub fn find_failed_response(&self) -> FindFailedResponse {
+        self.find_failed_response
+    }
+
+    /// Let `handler` decide about failed searches and switch the response
+    /// to `FindFailedResponse::Handle` (Java's `setFindFailedHandler`)
+    pub fn with_find_failed_handler(
+        mut self,
+        handler: impl Fn(&FindFailed<'_>) -> FindFailedAction + Send + Sync + 'static,
+    ) -> Self {
+        self.find_failed_handler = Some(FindFailedHandler::new(handler));
+        self.find_failed_response = FindFailedResponse::Handle;
+        self
+    }
+
+    /// Get the automated area
+    pub fn region(&self) -> Region {
+        self.region
+    }
+
+    /// Get the backends
+    pub fn backends(&self) -> &Backends {
+        &self.backends
+    }
+
+    /// Get an automated region with the same backends covering `region`
+    ///
+    /// The wait settings are kept.
+    pub fn with_region(&self, region: Region) -> AutoRegion {
+        AutoRegion {
+            region,
+            ..self.clone()
+        }
+    }
+
+    /// Capture the current content of the region
+    pub fn capture(&self) -> Result<ScreenImage> {
+        self.backends.screen.capture(self.region)
+    }
+
+    /// Search the region for `pattern` for up to the auto wait timeout
+    ///
+    /// Returns `Ok(None)` if the pattern did not appear in time.
+    pub fn exists(&self, pattern: impl Into<Pattern>) -> Result<Option<Match>> {
+        self.exists_within(pattern, self.auto_wait_timeout)
+    }
+
+    /// Search the region for `pattern` for up to `timeout`
+    ///
+    /// A zero `timeout` searches exactly once.
+    pub fn exists_within(
+        &self,
+        pattern: impl Into<Pattern>,
+        timeout: Duration,
+    ) -> Result<Option<Match>> {
+        let pattern = pattern.into();
+        self.poll(timeout, || self.search(&pattern))
+    }
+
+    /// Find the best match of `pattern` in the region, waiting for up to
+    /// the auto wait timeout
+    ///
+    /// # Errors
+    /// If the pattern did not appear, the find failed response decides:
+    /// `Error::PatternNotFound` on abort, `Error::FindSkipped` on skip and
+    /// `Error::FindAborted` if the handler aborts. Each carries a
+    /// [`FindFailure`] with the best candidate and any saved evidence.
+    pub fn find(&self, pattern: impl Into<Pattern>) -> Result<Match> {
+        let pattern = pattern.into();
+        self.find_or_fail(&pattern, self.auto_wait_timeout, Error::PatternNotFound)
+    }
+
+    /// Find all matches of `pattern` in the region, best first, waiting
+    /// for up to the auto wait timeout until there is at least one
+    ///
+    /// Unlike Java's `findAll`, a pattern that does not appear in time
+    /// gives empty matches rather than an error.
+    pub fn find_all(&self, pattern: impl Into<Pattern>) -> Result<Matches> {
+        let pattern = pattern.into();
+        let found = self.poll(self.auto_wait_timeout, || {
+            let image = self.capture()?;
+            let matches = self.backends.finder.find_all(&image, &pattern)?;
+            Ok((!matches.is_empty()).then_some(matches))
+        })?;
+        Ok(found.unwrap_or_default())
+    }
+
+    /// Search the region repeatedly until `pattern` appears
+    ///
+    /// # Errors
+    /// Like `find`, but aborting returns `Error::FindTimedOut`.
+    pub fn wait(&self, pattern: impl Into<Pattern>, timeout: Duration) -> Result<Match> {
+        let pattern = pattern.into();
+        self.find_or_fail(&pattern, timeout, Error::FindTimedOut)
+    }
+
+    /// Search the region repeatedly until `pattern` is no longer visible
+    ///
+    /// Returns whether the pattern vanished within `timeout`, like Java's
+    /// `waitVanish`.
+    pub fn wait_vanish(&self, pattern: impl Into<Pattern>, timeout: Duration) -> Result<bool> {
+        let pattern = pattern.into();
+        let vanished = self.poll(timeout, || {
+            Ok(self.search(&pattern)?.is_none().then_some(()))
+        })?;
+        Ok(vanished.is_some())
+    }
+
+    /// Find `pattern` and move the pointer to its target
+    pub fn hover(&self, pattern: impl Into<Pattern>) -> Result<Match> {
+        let found = self.find(pattern)?;
+        self.backends.input.move_to(found.target())?;
+        Ok(found)
+    }
+
+    /// Find `pattern` and left-click its target
+    pub fn click(&self, pattern: impl Into<Pattern>) -> Result<Match> {
+        self.click_with(pattern.into(), MouseButton::Left)
+    }
+
+    /// Find `pattern` and right-click its target
+    pub fn right_click(&self, pattern: impl Into<Pattern>) -> Result<Match> {
+        self.click_with(pattern.into(), MouseButton::Right)
+    }
+
+    /// Find `pattern` and double-click its target
+    pub fn double_click(&self, pattern: impl Into<Pattern>) -> Result<Match> {
+        let found = self.hover(pattern)?;
+        self.backends.input.double_click()?;
+        Ok(found)
+    }
+
+    /// Type `text` into the focused window
+    pub fn type_text(&self, text: &str) -> Result<()> {
+        self.backends.input.type_text(text)
+    }
+
+    /// Click `pattern` to focus it, then type `text`
+    pub fn type_into(&self, pattern: impl Into<Pattern>, text: &str) -> Result<Match> {
+        let found = self.click(pattern)?;
+        self.backends.input.type_text(text)?;
+        Ok(found)
+    }
+
+    /// Drag the target of `from` and drop it on the target of `to`
+    ///
+    /// Both patterns are searched before the mouse moves.
+    pub fn drag_drop(
+        &self,
+        from: impl Into<Pattern>,
+        to: impl Into<Pattern>,
+    ) -> Result<(Match, Match)> {
+        let from = self.find(from)?;
+        let to = self.find(to)?;
+        self.backends.input.drag_drop(from.target(), to.target())?;
+        Ok((from, to))
+    }
+
+    fn click_with(&self, pattern: Pattern, button: MouseButton) -> Result<Match> {
+        let found = self.hover(pattern)?;
+        self.backends.input.click(button)?;
+        Ok(found)
+    }
+
+    /// Wait for `pattern` and apply the find failed response if it does
+    /// not appear; `abort` makes the error of the `Abort` response
+    ///
+    /// Retries have no limit. Each one starts a scan interval after the
+    /// failed search, so retrying never spins.
+    fn find_or_fail(
+        &self,
+        pattern: &Pattern,
+        timeout: Duration,
+        abort: impl Fn(Box<FindFailure>) -> Error,
+    ) -> Result<Match> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let mut screenshot = None;
+            let found = self.poll(timeout, || {
+                let image = self.capture()?;
+                let found = self.backends.finder.find(&image, pattern)?;
+                screenshot = Some(image);
+                Ok(found)
+            })?;
+            if let Some(found) = found {
+                return Ok(found);
+            }
+            let screenshot = screenshot.expect("poll scans at least once");
+
+            let failure = || self.failure(pattern, &screenshot, timeout);
+            let action = match (self.find_failed_response, &self.find_failed_handler) {
+                (FindFailedResponse::Abort, _) => return Err(abort(failure())),
+                (FindFailedResponse::Skip, _) => FindFailedAction::Skip,
+                (FindFailedResponse::Retry, _) => FindFailedAction::Retry,
+                (FindFailedResponse::Prompt | FindFailedResponse::Handle, None) => {
+                    return Err(abort(failure()))
+                }
+                (FindFailedResponse::Prompt | FindFailedResponse::Handle, Some(handler)) => handler
+                    .decide(&FindFailed {
+                        pattern,
+                        region: self.region,
+                        screenshot: &screenshot,
+                        attempt,
+                    }),
+            };
+            match action {
+                FindFailedAction::Abort => return Err(Error::FindAborted(failure())),
+                FindFailedAction::Skip => return Err(Error::FindSkipped(failure())),
+                // Keep scanning at the scan rate, also with a zero timeout
+                FindFailedAction::Retry => self.backends.clock().sleep(self.scan_interval()),
+            }
+        }
+    }
+
+    /// Run `scan` until it returns a value or `timeout` has passed
+    ///
+    /// Scans start `1 / scan_rate` apart; a scan slower than that is
+    /// followed by the next one right away. The last scan may start at the
+    /// deadline, so a pattern appearing just in time is still seen.
+    fn poll<T>(
+        &self,
+        timeout: Duration,
+        mut scan: impl FnMut() -> Result<Option<T>>,
+    ) -> Result<Option<T>> {
+        let clock = self.backends.clock();
+        let interval = self.scan_interval();
+        // A timeout too long to represent never ends
+        let deadline = clock.now().checked_add(timeout);
+        loop {
+            let started = clock.now();
+            if let Some(found) = scan()? {
+                return Ok(Some(found));
+            }
+            let now = clock.now();
+            if deadline.is_some_and(|deadline| now >= deadline) {
+                return Ok(None);
+            }
+            let next = match deadline {
+                Some(deadline) => (started + interval).min(deadline),
+                None => started + interval,
+            };
+            if next > now {
+                clock.sleep(next - now);
+            }
+        }
+    }
+
+    /// Get the time between the starts of two scans
+    fn scan_interval(&self) -> Duration {
+        Duration::try_from_secs_f64(1.0 / self.scan_rate).map_or(MAX_SCAN_INTERVAL, |interval| {
+            interval.min(MAX_SCAN_INTERVAL)
+        })
+    }
+
+    fn search(&self, pattern: &Pattern) -> Result<Option<Match>> {
+        let image = self.capture()?;
+        self.backends.finder.find(&image, pattern)
+    }
+
+    /// Describe why `pattern` is not in `screenshot` after waiting for
+    /// `waited`, saving evidence if a writer is installed
+    fn failure(
+        &self,
+        pattern: &Pattern,
+        screenshot: &ScreenImage,
+        waited: Duration,
+    ) -> Box<FindFailure> {
+        let mut failure = FindFailure::new(pattern, self.region);
+        failure.waited = waited;
+        // The diagnostics are best effort and must not hide the failure
+        failure.best = self
+            .backends
+            .finder
+            .best_candidate(screenshot, pattern)
+            .ok()
+            .flatten();
+        if let Some(writer) = &self.backends.evidence {
+            if let Ok(evidence) = writer.save(pattern, screenshot, failure.best.as_ref()) {
+                failure.screenshot = Some(evidence.screenshot);
+                failure.diff = evidence.diff;
+            }
+        }
+        Box::new(failure)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use std::ops::Range;
+    use std::sync::Mutex;
+
+    /// A black screen
+    struct BlankScreen;
+
+    impl ScreenBackend for BlankScreen {
+        fn bounds(&self) -> Result<Region> {
+            Ok(Region::new(0, 0, 200, 100))
+        }
+
+        fn capture(&self, region: Region) -> Result<ScreenImage> {
+            Ok(ScreenImage::new(region))
+        }
+    }
+
+    /// Finds "ok.png" at a fixed place, if it is inside the captured area
+    struct FixedFinder;
+
+    impl FinderBackend for FixedFinder {
+        fn find(&self, image: &ScreenImage, pattern: &Pattern) -> Result<Option<Match>> {
+            let region = Region::new(50, 40, 20, 10);
+            let visible = pattern.image.path() == "ok.png"
+                && image.region().intersection(&region) == Some(region);
+            Ok(visible.then(|| Match::new(region, 0.95).with_offset(pattern.target_offset)))
+        }
+
+        fn find_all(&self, image: &ScreenImage, pattern: &Pattern) -> Result<Matches> {
+            Ok(self.find(image, pattern)?.into_iter().collect())
+        }
+    }
+
+    /// Finds "ok.png" at a fixed place while the clock is in `shown`
+    struct TimedFinder {
+        clock: Arc<ManualClock>,
+        shown: Range<Duration>,
+    }
+
+    impl FinderBackend for TimedFinder {
+        fn find(&self, image: &ScreenImage, pattern: &Pattern) -> Result<Option<Match>> {
+            if !self.shown.contains(&self.clock.elapsed()) {
+                return Ok(None);
+            }
+            FixedFinder.find(image, pattern)
+        }
+
+        fn find_all(&self, image: &ScreenImage, pattern: &Pattern) -> Result<Matches> {
+            Ok(self.find(image, pattern)?.into_iter().collect())
+        }
+    }
+
+    /// Sees "ok.png" at a fixed place, but only with a score of 0.5
+    struct BlurryFinder;
+
+    impl FinderBackend for BlurryFinder {
+        fn find(&self, image: &ScreenImage, pattern: &Pattern) -> Result<Option<Match>> {
+            let found = FixedFinder.find(image, pattern)?;
+            Ok(found
+                .map(|m| Match::new(m.region, 0.5))
+                .filter(|m| m.score >= pattern.similarity))
+        }
+
+        fn find_all(&self, image: &ScreenImage, pattern: &Pattern) -> Result<Matches> {
+            Ok(self.find(image, pattern)?.into_iter().collect())
+        }
+    }
+
+    /// Pretends to save evidence under /evidence
+    struct FakeEvidence;
+
+    impl EvidenceWriter for FakeEvidence {
+        fn save(
+            &self,
+            pattern: &Pattern,
+            _screenshot: &ScreenImage,
+            best: Option<&Match>,
+        ) -> Result<crate::Evidence> {
+            let dir = std::path::Path::new("/evidence");
+            Ok(crate::Evidence {
+                screenshot: dir.join("screen.png"),
+                diff: best.map(|_| dir.join(format!("{}-diff.png", pattern.image.path()))),
+            })
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingInput {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl RecordingInput {
+        fn record(&self, event: String) -> Result<()> {
+            self.events.lock().unwrap().push(event);
+            Ok(())
+        }
+    }
+
+    impl InputBackend for RecordingInput {
+        fn move_to(&self, location: Location) -> Result<()> {
+            self.record(format!("move {} {}", location.x, location.y))
+        }
+
+        fn button_down(&self, button: MouseButton) -> Result<()> {
+            self.record(format!("down {:?}", button))
+        }
+
+        fn button_up(&self, button: MouseButton) -> Result<()> {
+            self.record(format!("up {:?}", button))
+        }
+
+        fn type_text(&self, text: &str) -> Result<()> {
+            self.record(format!("type {}", text))
+        }
+    }
+
+    fn setup() -> (AutoRegion, Arc<RecordingInput>) {
+        let input = Arc::new(RecordingInput::default());
+        let backends =
+            Backends::from_shared(Arc::new(BlankScreen), input.clone(), Arc::new(FixedFinder))
+                .with_clock(Arc::new(ManualClock::new()));
+        (backends.whole_screen().unwrap(), input)
+    }
+
+    /// A screen where "ok.png" is visible while the clock is in `shown`
+    fn timed_setup(shown: Range<Duration>) -> (AutoRegion, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock::new());
+        let finder = TimedFinder {
+            clock: clock.clone(),
+            shown,
+        };
+        let backends =
+            Backends::new(BlankScreen, RecordingInput::default(), finder).with_clock(clock.clone());
+        (backends.whole_screen().unwrap(), clock)
+    }
+
+    fn secs(secs: f64) -> Duration {
+        Duration::from_secs_f64(secs)
+    }
+
+    #[test]
+    fn test_click_and_type_at_match_target() {
+        let (screen, input) = setup();
+        let pattern = Pattern::from("ok.png").target_offset(crate::Offset::new(5, 0));
+
+        let found = screen.type_into(pattern, "hi").unwrap();
+        assert_eq!(found.score, 0.95);
+        assert_eq!(
+            *input.events.lock().unwrap(),
+            vec!["move 65 45", "down Left", "up Left", "type hi"]
+        );
+    }
+
+    #[test]
+    fn test_find_is_limited_to_region() {
+        let (screen, input) = setup();
+        assert!(screen.exists("ok.png").unwrap().is_some());
+
+        let left = screen.with_region(Region::new(0, 0, 50, 100));
+        assert!(matches!(
+            left.click("ok.png"),
+            Err(Error::PatternNotFound(_))
+        ));
+        assert!(input.events.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_wait_times_out() {
+        let (screen, clock) = timed_setup(secs(10.0)..secs(20.0));
+        let result = screen.wait("ok.png", secs(2.0));
+        let Err(Error::FindTimedOut(failure)) = result else {
+            panic!("wait should time out");
+        };
+        assert_eq!(failure.waited, secs(2.0));
+        assert_eq!(failure.pattern, "ok.png");
+        assert_eq!(clock.elapsed(), secs(2.0));
+    }
+
+    #[test]
+    fn test_wait_scans_at_scan_rate() {
+        let (screen, clock) = timed_setup(secs(1.0)..secs(20.0));
+        let found = screen.wait("ok.png", secs(5.0)).unwrap();
+        assert_eq!(found.region, Region::new(50, 40, 20, 10));
+        // Scans at 0, 1/3, 2/3, 3/3 s; rounding may defer the hit to 4/3 s
+        assert!(clock.elapsed() >= secs(1.0) && clock.elapsed() < secs(1.4));
+
+        let (screen, clock) = timed_setup(secs(1.0)..secs(20.0));
+        let screen = screen.with_scan_rate(0.5).unwrap();
+        screen.wait("ok.png", secs(5.0)).unwrap();
+        assert_eq!(clock.elapsed(), secs(2.0));
+    }
+
+    #[test]
+    fn test_scan_rate_validation() {
+        let (screen, clock) = timed_setup(secs(1.0)..secs(20.0));
+        for rate in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                screen.clone().with_scan_rate(rate),
+                Err(Error::InvalidParameter(_))
+            ));
+        }
+
+        // Tiny rates do not overflow; the second scan is at the deadline
+        let slow = screen.with_scan_rate(f64::MIN_POSITIVE).unwrap();
+        assert!(slow.wait("ok.png", secs(5.0)).is_ok());
+        assert_eq!(clock.elapsed(), secs(5.0));
+    }
+
+    #[test]
+    fn test_wait_without_deadline() {
+        let (screen, clock) = timed_setup(secs(10.0)..secs(20.0));
+        let screen = screen.with_auto_wait_timeout(Duration::MAX);
+        assert!(screen.exists("ok.png").unwrap().is_some());
+        assert!(clock.elapsed() >= secs(10.0) && clock.elapsed() < secs(10.4));
+    }
+
+    #[test]
+    fn test_exists_uses_auto_wait_timeout() {
+        let (screen, clock) = timed_setup(secs(10.0)..secs(20.0));
+        assert!(screen.exists("ok.png").unwrap().is_none());
+        assert_eq!(clock.elapsed(), DEFAULT_AUTO_WAIT_TIMEOUT);
+
+        let screen = screen.with_auto_wait_timeout(Duration::ZERO);
+        assert!(matches!(
+            screen.find("ok.png"),
+            Err(Error::PatternNotFound(_))
+        ));
+        assert_eq!(clock.elapsed(), DEFAULT_AUTO_WAIT_TIMEOUT);
+
+        let narrow = screen.with_region(Region::new(0, 0, 50, 50));
+        assert_eq!(narrow.auto_wait_timeout(), Duration::ZERO);
+        assert!(screen.exists_within("ok.png", secs(8.0)).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_find_all_uses_auto_wait_timeout() {
+        let (screen, clock) = timed_setup(secs(10.0)..secs(20.0));
+        assert!(screen.find_all("ok.png").unwrap().is_empty());
+        assert_eq!(clock.elapsed(), DEFAULT_AUTO_WAIT_TIMEOUT);
+
+        let screen = screen.with_auto_wait_timeout(secs(10.0));
+        assert_eq!(screen.find_all("ok.png").unwrap().len(), 1);
+        assert!(clock.elapsed() >= secs(10.0) && clock.elapsed() < secs(10.4));
+    }
+
+    #[test]
+    fn test_wait_vanish() {
+        let (screen, clock) = timed_setup(Duration::ZERO..secs(1.0));
+        assert!(screen.wait_vanish("ok.png", secs(5.0)).unwrap());
+        assert!(clock.elapsed() >= secs(1.0) && clock.elapsed() < secs(1.4));
+
+        let (screen, clock) = timed_setup(Duration::ZERO..secs(20.0));
+        assert!(!screen.wait_vanish("ok.png", secs(2.0)).unwrap());
+        assert_eq!(clock.elapsed(), secs(2.0));
+    }
+
+    #[test]
+    fn test_not_found_reports_best_candidate() {
+        let backends = Backends::new(BlankScreen, RecordingInput::default(), BlurryFinder)
+            .with_clock(Arc::new(ManualClock::new()));
+        let screen = backends.whole_screen().unwrap();
+        let Err(Error::PatternNotFound(failure)) = screen.find("ok.png") else {
+            panic!("a score of 0.5 should not be found");
+        };
+        assert_eq!(failure.pattern, "ok.png");
+        assert_eq!(failure.region, Region::new(0, 0, 200, 100));
+        assert_eq!(failure.required, 0.7);
+        let best = failure.best.as_ref().unwrap();
+        assert_eq!(
+            (best.region, best.score),
+            (Region::new(50, 40, 20, 10), 0.5)
+        );
+        assert!(failure.screenshot.is_none());
+
+        let screen = backends
+            .with_evidence(Arc::new(FakeEvidence))
+            .region(Region::new(0, 0, 10, 10))
+            .with_auto_wait_timeout(Duration::ZERO);
+        let error = screen.find("ok.png").unwrap_err();
+        let Error::PatternNotFound(failure) = &error else {
+            panic!("unexpected error {:?}", error);
+        };
+        assert!(failure.best.is_none());
+        assert_eq!(
+            failure.screenshot.as_deref(),
+            Some("/evidence/screen.png".as_ref())
+        );
+        assert_eq!(
+            error.to_string(),
+            "Pattern match failed: ok.png in Region { x: 0, y: 0, w: 10, h: 10 }, \
+             no candidate, screenshot /evidence/screen.png"
+        );
+    }
+
+    #[test]
+    fn test_find_failed_responses() {
+        let (screen, clock) = timed_setup(secs(100.0)..secs(200.0));
+        let skip = screen
+            .clone()
+            .with_find_failed_response(FindFailedResponse::Skip);
+        assert!(matches!(skip.click("ok.png"), Err(Error::FindSkipped(_))));
+
+        // Prompt and Handle abort without a handler
+        let prompt = screen
+            .clone()
+            .with_find_failed_response(FindFailedResponse::Prompt);
+        assert!(matches!(
+            prompt.find("ok.png"),
+            Err(Error::PatternNotFound(_))
+        ));
+        assert!(matches!(
+            prompt.wait("ok.png", secs(1.0)),
+            Err(Error::FindTimedOut(_))
+        ));
+
+        let retry = screen.with_find_failed_response(FindFailedResponse::Retry);
+        assert!(retry.find("ok.png").is_ok());
+        assert!(clock.elapsed() >= secs(100.0));
+    }
+
+    #[test]
+    fn test_retry_waits_between_attempts() {
+        let (screen, clock) = timed_setup(secs(1.0)..secs(20.0));
+        let attempts = Arc::new(Mutex::new(0));
+        let counted = attempts.clone();
+        let screen = screen
+            .with_auto_wait_timeout(Duration::ZERO)
+            .with_find_failed_handler(move |_| {
+                *counted.lock().unwrap() += 1;
+                FindFailedAction::Retry
+            });
+
+        // One scan per attempt, 1/3 s apart
+        screen.find("ok.png").unwrap();
+        assert!(clock.elapsed() >= secs(1.0) && clock.elapsed() < secs(1.4));
+        assert!((3..=4).contains(&*attempts.lock().unwrap()));
+    }
+
+    #[test]
+    fn test_find_failed_handler_decides() {
+        let (screen, clock) = timed_setup(secs(7.0)..secs(20.0));
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let recorded = seen.clone();
+        let screen = screen.with_find_failed_handler(move |failed| {
+            recorded.lock().unwrap().push((
+                failed.attempt,
+                failed.pattern.image.path().to_string(),
+                failed.screenshot.region(),
+            ));
+            FindFailedAction::Retry
+        });
+        assert_eq!(screen.find_failed_response(), FindFailedResponse::Handle);
+
+        // Two searches of 3s each fail before the pattern appears at 7s
+        screen.find("ok.png").unwrap();
+        assert!(clock.elapsed() >= secs(7.0));
+        let bounds = Region::new(0, 0, 200, 100);
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                (1, "ok.png".to_string(), bounds),
+                (2, "ok.png".to_string(), bounds)
+            ]
+        );
+
+        let abort = screen
+            .clone()
+            .with_find_failed_handler(|_| FindFailedAction::Abort);
+        assert!(matches!(
+            abort.find("missing.png"),
+            Err(Error::FindAborted(failure)) if failure.pattern == "missing.png"
+        ));
+        let skip = screen.with_find_failed_handler(|_| FindFailedAction::Skip);
+        assert!(matches!(
+            skip.wait("missing.png", secs(1.0)),
+            Err(Error::FindSkipped(_))
+        ));
+    }
+
+    #[test]
+    fn test_drag_drop_between_matches() {
+        let (screen, input) = setup();
+        screen.drag_drop("ok.png", "ok.png").unwrap();
+        assert_eq!(
+            *input.events.lock().unwrap(),
+            vec!["move 60 45", "down Left", "move 60 45", "up Left"]
+        );
+    }
+}
@@ -1,5 +1,6 @@
 //! Error types for SikuliX
 
+use crate::find_failed::FindFailure;
 use thiserror::Error;
 
 /// Result type alias for SikuliX operations
@@ -12,7 +13,7 @@ pub enum Error {
     ImageNotFound(String),
 
     #[error("Pattern match failed: {0}")]
-    PatternNotFound(String),
+    PatternNotFound(Box<FindFailure>),
 
     #[error("Invalid region: {0}")]
     InvalidRegion(String),
@@ -29,6 +30,15 @@ pub enum Error {
     #[error("Timeout waiting for pattern after {0}s")]
     Timeout(f64),
 
+    #[error("Timeout waiting for pattern after {}s: {}", .0.waited.as_secs_f64(), .0)]
+    FindTimedOut(Box<FindFailure>),
+
+    #[error("Search skipped: {0}")]
+    FindSkipped(Box<FindFailure>),
+
+    #[error("Search aborted: {0}")]
+    FindAborted(Box<FindFailure>),
+
     #[error("Invalid parameter: {0}")]
     InvalidParameter(String),
 
@@ -0,0 +1,173 @@
+//! What an `AutoRegion` does when a pattern cannot be found
+//!
+//! Like Java's `FindFailedResponse`, a region either aborts, skips, retries
+//! or asks a [`FindFailedHandler`], which sees the pattern and the last
+//! screenshot and decides with a [`FindFailedAction`].
+//!
+//! A search that finally fails reports a [`FindFailure`] with the best
+//! candidate it saw and, if an [`EvidenceWriter`] is installed, the files
+//! it saved for debugging.
+
+use crate::{Match, Pattern, Region, Result, ScreenImage};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Policy of an `AutoRegion` for searches that fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FindFailedResponse {
+    /// Fail with `Error::PatternNotFound`, or `Error::FindTimedOut` for `wait`
+    #[default]
+    Abort,
+
+    /// Fail with `Error::FindSkipped`, which scripts may ignore
+    Skip,
+
+    /// Search again until the pattern appears, without a limit; the
+    /// searches stay a scan interval apart
+    Retry,
+
+    /// Ask the user through the handler, e.g. a dialog installed by a GUI;
+    /// aborts like `Abort` if no handler is set
+    Prompt,
+
+    /// Let the handler decide; aborts like `Abort` if no handler is set
+    Handle,
+}
+
+/// Decision of a `FindFailedHandler`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FindFailedAction {
+    /// Fail with `Error::FindAborted`
+    Abort,
+
+    /// Fail with `Error::FindSkipped`
+    Skip,
+
+    /// Search again, waiting as long as the failed search did
+    Retry,
+}
+
+/// What a `FindFailedHandler` gets to see about a failed search
+#[derive(Debug)]
+pub struct FindFailed<'a> {
+    /// The pattern that was not found
+    pub pattern: &'a Pattern,
+
+    /// The searched area
+    pub region: Region,
+
+    /// The last capture of the searched area
+    pub screenshot: &'a ScreenImage,
+
+    /// Number of failed searches so far, starting at 1
+    pub attempt: u32,
+}
+
+/// Callback deciding how to go on after a failed search
+#[derive(Clone)]
+pub struct FindFailedHandler(Arc<dyn Fn(&FindFailed<'_>) -> FindFailedAction + Send + Sync>);
+
+impl FindFailedHandler {
+    /// Wrap `handler`
+    pub fn new(
+        handler: impl Fn(&FindFailed<'_>) -> FindFailedAction + Send + Sync + 'static,
+    ) -> Self {
+        Self(Arc::new(handler))
+    }
+
+    /// Ask the handler about `failed`
+    pub fn decide(&self, failed: &FindFailed<'_>) -> FindFailedAction {
+        (self.0)(failed)
+    }
+}
+
+impl fmt::Debug for FindFailedHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FindFailedHandler").finish_non_exhaustive()
+    }
+}
+
+/// Details of `Error::PatternNotFound`, `Error::FindTimedOut`,
+/// `Error::FindSkipped` and `Error::FindAborted`
+#[derive(Debug, Clone)]
+pub struct FindFailure {
+    /// Path of the pattern image
+    pub pattern: String,
+
+    /// The searched area
+    pub region: Region,
+
+    /// How long the search waited for the pattern
+    pub waited: Duration,
+
+    /// Similarity the pattern required
+    pub required: f32,
+
+    /// Best candidate in the last screenshot, whatever its score
+    pub best: Option<Match>,
+
+    /// Saved copy of the last screenshot
+    pub screenshot: Option<PathBuf>,
+
+    /// Saved image comparing the pattern with the best candidate
+    pub diff: Option<PathBuf>,
+}
+
+impl FindFailure {
+    /// Report that `pattern` was not found in `region`, without details
+    pub fn new(pattern: &Pattern, region: Region) -> Self {
+        Self {
+            pattern: pattern.image.path().to_string(),
+            region,
+            waited: Duration::ZERO,
+            required: pattern.similarity,
+            best: None,
+            screenshot: None,
+            diff: None,
+        }
+    }
+}
+
+impl fmt::Display for FindFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} in {:?}", self.pattern, self.region)?;
+        match &self.best {
+            Some(best) => write!(
+                f,
+                ", best candidate scored {:.3} (required {:.3}) at {:?}",
+                best.score, self.required, best.region
+            )?,
+            None => write!(f, ", no candidate")?,
+        }
+        if let Some(path) = &self.screenshot {
+            write!(f, ", screenshot {}", path.display())?;
+        }
+        if let Some(path) = &self.diff {
+            write!(f, ", diff {}", path.display())?;
+        }
+        Ok(())
+    }
+}
+
+/// Files saved by an `EvidenceWriter`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Evidence {
+    /// Copy of the searched screenshot
+    pub screenshot: PathBuf,
+
+    /// Image comparing the pattern with the best candidate, if there is one
+    pub diff: Option<PathBuf>,
+}
+
+/// Saves what a failed search saw, so failures can be debugged afterwards
+pub trait EvidenceWriter: Send + Sync {
+    /// Save `screenshot` and a comparison of `pattern` with `best`
+    fn save(
+        &self,
+        pattern: &Pattern,
+        screenshot: &ScreenImage,
+        best: Option<&Match>,
+    ) -> Result<Evidence>;
+}
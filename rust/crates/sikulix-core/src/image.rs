@@ -1,5 +1,7 @@
 //! Image representation for SikuliX
 
+use crate::image_header::probe_dimensions;
+use crate::{Error, ImagePath, Result};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -17,6 +19,11 @@ pub struct Image {
 
     /// Height of the image in pixels
     pub height: u32,
+
+    /// File found by [`Image::resolve`], kept as is because `path` cannot
+    /// hold file names that are not UTF-8
+    #[serde(skip)]
+    resolved: Option<PathBuf>,
 }
 
 impl Image {
@@ -26,6 +33,7 @@ impl Image {
             path,
             width,
             height,
+            resolved: None,
         }
     }
 
@@ -35,9 +43,31 @@ impl Image {
             path: path.into(),
             width: 0, // Will be loaded when needed
             height: 0,
+            resolved: None,
         }
     }
 
+    /// Create an image reference for `name`, looked up in the global
+    /// [`ImagePath`]
+    ///
+    /// # Errors
+    /// Returns `Error::ImageNotFound` listing the searched locations.
+    pub fn resolve(name: &str) -> Result<Self> {
+        Self::resolve_in(name, &ImagePath::global())
+    }
+
+    /// Create an image reference for `name`, looked up in `image_path`
+    ///
+    /// `path` shows the file found, and [`Image::path_buf`] returns it
+    /// unchanged, also when its name is not UTF-8.
+    pub fn resolve_in(name: &str, image_path: &ImagePath) -> Result<Self> {
+        let path = image_path.resolve(name)?;
+        Ok(Self {
+            resolved: Some(path.clone()),
+            ..Self::from_path(path.to_string_lossy())
+        })
+    }
+
     /// Get the image path
     pub fn path(&self) -> &str {
         &self.path
@@ -53,9 +83,33 @@ impl Image {
         self.width > 0 && self.height > 0
     }
 
+    /// Get the image dimensions, reading them from the file header the
+    /// first time
+    ///
+    /// Only PNG, JPEG, BMP, WebP and TIFF headers are understood; the vision
+    /// crate's `ImageLoader::load_dimensions` also handles other formats.
+    ///
+    /// # Errors
+    /// Returns `Error::Io` if the file cannot be read and
+    /// `Error::InvalidPattern` if its format is not recognized.
+    pub fn load_dimensions(&mut self) -> Result<(u32, u32)> {
+        if !self.has_dimensions() {
+            let (width, height) = probe_dimensions(self.path_buf())?.ok_or_else(|| {
+                Error::InvalidPattern(format!("Unrecognized image format: {}", self.path))
+            })?;
+            self.width = width;
+            self.height = height;
+        }
+        Ok(self.dimensions())
+    }
+
     /// Convert path to PathBuf
+    ///
+    /// Images created by [`Image::resolve`] return the resolved file.
     pub fn path_buf(&self) -> PathBuf {
-        PathBuf::from(&self.path)
+        self.resolved
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(&self.path))
     }
 }
 
@@ -90,6 +144,49 @@ mod tests {
         assert!(!img.has_dimensions());
     }
 
+    #[test]
+    fn test_load_dimensions_from_header() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("button.png");
+        std::fs::write(&path, crate::image_header::tests::png_header(37, 23)).unwrap();
+
+        let mut img = Image::from_path(path.to_string_lossy());
+        assert_eq!(img.load_dimensions().unwrap(), (37, 23));
+        assert_eq!((img.width, img.height), (37, 23));
+
+        // Known dimensions are not read again
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(img.load_dimensions().unwrap(), (37, 23));
+
+        let unknown = dir.path().join("button.ppm");
+        std::fs::write(&unknown, b"P6\n37 23\n255\n").unwrap();
+        let mut img = Image::from_path(unknown.to_string_lossy());
+        assert!(matches!(
+            img.load_dimensions(),
+            Err(Error::InvalidPattern(_))
+        ));
+        assert!(!img.has_dimensions());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_keeps_non_utf8_paths() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let images = dir.path().join(std::ffi::OsStr::from_bytes(b"caf\xe9"));
+        std::fs::create_dir(&images).unwrap();
+        let path = images.join("button.png");
+        std::fs::write(&path, crate::image_header::tests::png_header(37, 23)).unwrap();
+        let mut image_path = ImagePath::new();
+        image_path.add(&images);
+
+        let mut img = Image::resolve_in("button", &image_path).unwrap();
+        assert_eq!(img.path_buf(), path);
+        assert_eq!(img.path(), path.to_string_lossy());
+        assert_eq!(img.load_dimensions().unwrap(), (37, 23));
+    }
+
     #[test]
     fn test_image_from_string() {
         let img: Image = "button.png".into();
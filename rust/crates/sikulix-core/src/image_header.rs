@@ -0,0 +1,307 @@
+//! Image dimensions read from file headers, without decoding the pixels
+//!
+//! Supports PNG, JPEG, BMP, WebP and TIFF. Other formats, and files whose
+//! header cannot be understood, yield `None` so callers can fall back to
+//! decoding the image.
+
+use crate::Result;
+use std::fs::File;
+use std::io::{self, BufReader, ErrorKind, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Read the width and height of the image file at `path` from its header
+///
+/// Returns `Ok(None)` for unknown formats and malformed headers.
+///
+/// # Errors
+/// Returns `Error::Io` if the file cannot be opened or read.
+pub fn probe_dimensions(path: impl AsRef<Path>) -> Result<Option<(u32, u32)>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    Ok(read_dimensions(&mut reader)?)
+}
+
+/// Read the width and height of an image from its header
+///
+/// Returns `Ok(None)` for unknown formats and malformed headers.
+pub fn read_dimensions<R: Read + Seek>(reader: &mut R) -> io::Result<Option<(u32, u32)>> {
+    let mut magic = [0u8; 16];
+    let len = read_up_to(reader, &mut magic)?;
+    let magic = &magic[..len];
+    reader.seek(SeekFrom::Start(0))?;
+
+    let parsed = if magic.starts_with(b"\x89PNG\r\n\x1a\n") {
+        png(reader)
+    } else if magic.starts_with(&[0xFF, 0xD8]) {
+        jpeg(reader)
+    } else if magic.starts_with(b"BM") {
+        bmp(reader)
+    } else if magic.starts_with(b"RIFF") && magic.get(8..12) == Some(b"WEBP") {
+        webp(reader)
+    } else if magic.starts_with(b"II*\0") || magic.starts_with(b"MM\0*") {
+        tiff(reader)
+    } else {
+        return Ok(None);
+    };
+
+    match parsed {
+        Ok(Some((w, h))) if w > 0 && h > 0 => Ok(Some((w, h))),
+        Ok(_) => Ok(None),
+        // A truncated header is as good as an unknown one
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Signature, then the IHDR chunk starting with width and height
+fn png<R: Read + Seek>(reader: &mut R) -> io::Result<Option<(u32, u32)>> {
+    let mut header = [0u8; 24];
+    reader.read_exact(&mut header)?;
+    if &header[12..16] != b"IHDR" {
+        return Ok(None);
+    }
+    Ok(Some((be_u32(&header[16..20]), be_u32(&header[20..24]))))
+}
+
+/// Walk the marker segments up to the first start-of-frame
+fn jpeg<R: Read + Seek>(reader: &mut R) -> io::Result<Option<(u32, u32)>> {
+    reader.seek(SeekFrom::Start(2))?;
+    loop {
+        if read_u8(reader)? != 0xFF {
+            return Ok(None);
+        }
+        let mut marker = read_u8(reader)?;
+        while marker == 0xFF {
+            marker = read_u8(reader)?;
+        }
+        match marker {
+            // Markers without a segment
+            0x01 | 0xD0..=0xD7 => continue,
+            // Image data or end of image before any frame
+            0xD9 | 0xDA => return Ok(None),
+            _ => {}
+        }
+
+        let mut length = [0u8; 2];
+        reader.read_exact(&mut length)?;
+        let length = be_u16(&length);
+        if length < 2 {
+            return Ok(None);
+        }
+        // SOF0..SOF15, except DHT, JPG and DAC which share the range
+        if (0xC0..=0xCF).contains(&marker) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+            let mut frame = [0u8; 5];
+            reader.read_exact(&mut frame)?;
+            let height = be_u16(&frame[1..3]);
+            let width = be_u16(&frame[3..5]);
+            return Ok(Some((width.into(), height.into())));
+        }
+        reader.seek(SeekFrom::Current(i64::from(length) - 2))?;
+    }
+}
+
+/// File header, then a DIB header whose layout depends on its size
+fn bmp<R: Read + Seek>(reader: &mut R) -> io::Result<Option<(u32, u32)>> {
+    let mut header = [0u8; 26];
+    reader.read_exact(&mut header)?;
+    let dib_size = le_u32(&header[14..18]);
+    if dib_size == 12 {
+        // OS/2 BITMAPCOREHEADER with 16-bit sizes
+        return Ok(Some((
+            le_u16(&header[18..20]).into(),
+            le_u16(&header[20..22]).into(),
+        )));
+    }
+    let width = le_u32(&header[18..22]) as i32;
+    // Negative heights mark top-down bitmaps
+    let height = le_u32(&header[22..26]) as i32;
+    Ok(Some((width.unsigned_abs(), height.unsigned_abs())))
+}
+
+/// RIFF container with a lossy, lossless or extended first chunk
+fn webp<R: Read + Seek>(reader: &mut R) -> io::Result<Option<(u32, u32)>> {
+    let mut header = [0u8; 30];
+    let len = read_up_to(reader, &mut header)?;
+    // The lossless header is shorter than the other two
+    let needed = if &header[12..16] == b"VP8L" { 25 } else { 30 };
+    if len < needed {
+        return Ok(None);
+    }
+    let dimensions = match &header[12..16] {
+        b"VP8 " => {
+            if header[23..26] != [0x9D, 0x01, 0x2A] {
+                return Ok(None);
+            }
+            let width = le_u16(&header[26..28]) & 0x3FFF;
+            let height = le_u16(&header[28..30]) & 0x3FFF;
+            (width.into(), height.into())
+        }
+        b"VP8L" => {
+            if header[20] != 0x2F {
+                return Ok(None);
+            }
+            let bits = le_u32(&header[21..25]);
+            (1 + (bits & 0x3FFF), 1 + ((bits >> 14) & 0x3FFF))
+        }
+        b"VP8X" => (1 + le_u24(&header[24..27]), 1 + le_u24(&header[27..30])),
+        _ => return Ok(None),
+    };
+    Ok(Some(dimensions))
+}
+
+/// ImageWidth and ImageLength tags of the first image file directory
+fn tiff<R: Read + Seek>(reader: &mut R) -> io::Result<Option<(u32, u32)>> {
+    let mut header = [0u8; 8];
+    reader.read_exact(&mut header)?;
+    let little = &header[..2] == b"II";
+    let u16_at = |b: &[u8]| if little { le_u16(b) } else { be_u16(b) };
+    let u32_at = |b: &[u8]| if little { le_u32(b) } else { be_u32(b) };
+
+    reader.seek(SeekFrom::Start(u32_at(&header[4..8]).into()))?;
+    let mut count = [0u8; 2];
+    reader.read_exact(&mut count)?;
+
+    let (mut width, mut height) = (None, None);
+    for _ in 0..u16_at(&count) {
+        let mut entry = [0u8; 12];
+        reader.read_exact(&mut entry)?;
+        let value = match u16_at(&entry[2..4]) {
+            // SHORT, stored in the first two bytes of the value field
+            3 => u32::from(u16_at(&entry[8..10])),
+            // LONG
+            4 => u32_at(&entry[8..12]),
+            _ => continue,
+        };
+        match u16_at(&entry[0..2]) {
+            256 => width = Some(value),
+            257 => height = Some(value),
+            _ => {}
+        }
+        if let (Some(w), Some(h)) = (width, height) {
+            return Ok(Some((w, h)));
+        }
+    }
+    Ok(None)
+}
+
+/// Fill as much of `buf` as the reader has, returning the count
+fn read_up_to<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> io::Result<u8> {
+    let mut byte = [0u8; 1];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn be_u16(b: &[u8]) -> u16 {
+    u16::from_be_bytes([b[0], b[1]])
+}
+
+fn le_u16(b: &[u8]) -> u16 {
+    u16::from_le_bytes([b[0], b[1]])
+}
+
+fn be_u32(b: &[u8]) -> u32 {
+    u32::from_be_bytes([b[0], b[1], b[2], b[3]])
+}
+
+fn le_u32(b: &[u8]) -> u32 {
+    u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+}
+
+fn le_u24(b: &[u8]) -> u32 {
+    u32::from_le_bytes([b[0], b[1], b[2], 0])
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+        read_dimensions(&mut Cursor::new(bytes)).unwrap()
+    }
+
+    /// PNG signature and IHDR chunk, without any image data
+    pub(crate) fn png_header(width: u32, height: u32) -> Vec<u8> {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend_from_slice(&width.to_be_bytes());
+        png.extend_from_slice(&height.to_be_bytes());
+        png.extend_from_slice(&[8, 2, 0, 0, 0]);
+        png
+    }
+
+    #[test]
+    fn test_png() {
+        assert_eq!(dimensions(&png_header(37, 23)), Some((37, 23)));
+        assert_eq!(dimensions(&png_header(0, 23)), None);
+    }
+
+    #[test]
+    fn test_jpeg_skips_segments_before_frame() {
+        let mut jpeg = b"\xff\xd8\xff\xe0\0\x10JFIF\0\x01\x01\0\0\x01\0\x01\0\0".to_vec();
+        // Fill bytes, then a progressive frame header
+        jpeg.extend_from_slice(b"\xff\xff\xc2\0\x11\x08\0\x17\0\x25\x03");
+        assert_eq!(dimensions(&jpeg), Some((37, 23)));
+    }
+
+    #[test]
+    fn test_bmp_headers() {
+        let mut bmp = b"BM\0\0\0\0\0\0\0\0\x36\0\0\0\x28\0\0\0".to_vec();
+        bmp.extend_from_slice(&37i32.to_le_bytes());
+        bmp.extend_from_slice(&(-23i32).to_le_bytes());
+        assert_eq!(dimensions(&bmp), Some((37, 23)));
+
+        let mut core = b"BM\0\0\0\0\0\0\0\0\x1a\0\0\0\x0c\0\0\0".to_vec();
+        core.extend_from_slice(&[37, 0, 23, 0, 1, 0, 24, 0]);
+        assert_eq!(dimensions(&core), Some((37, 23)));
+    }
+
+    #[test]
+    fn test_webp_variants() {
+        let mut lossy = b"RIFF\0\0\0\0WEBPVP8 \0\0\0\0\0\0\0\x9d\x01\x2a".to_vec();
+        lossy.extend_from_slice(&[37, 0, 23, 0]);
+        assert_eq!(dimensions(&lossy), Some((37, 23)));
+
+        let bits: u32 = 36 | (22 << 14);
+        let mut lossless = b"RIFF\0\0\0\0WEBPVP8L\0\0\0\0\x2f".to_vec();
+        lossless.extend_from_slice(&bits.to_le_bytes());
+        assert_eq!(dimensions(&lossless), Some((37, 23)));
+
+        let mut extended = b"RIFF\0\0\0\0WEBPVP8X\0\0\0\0\0\0\0\0".to_vec();
+        extended.extend_from_slice(&[36, 0, 0, 22, 0, 0]);
+        assert_eq!(dimensions(&extended), Some((37, 23)));
+    }
+
+    #[test]
+    fn test_tiff_byte_orders() {
+        // One IFD at offset 8 with a SHORT width and a LONG height
+        let mut little = b"II*\0\x08\0\0\0\x02\0".to_vec();
+        little.extend_from_slice(&[0, 1, 3, 0, 1, 0, 0, 0, 37, 0, 0, 0]);
+        little.extend_from_slice(&[1, 1, 4, 0, 1, 0, 0, 0, 23, 0, 0, 0]);
+        assert_eq!(dimensions(&little), Some((37, 23)));
+
+        let mut big = b"MM\0*\0\0\0\x08\0\x02".to_vec();
+        big.extend_from_slice(&[1, 0, 0, 3, 0, 0, 0, 1, 0, 37, 0, 0]);
+        big.extend_from_slice(&[1, 1, 0, 4, 0, 0, 0, 1, 0, 0, 0, 23]);
+        assert_eq!(dimensions(&big), Some((37, 23)));
+    }
+
+    #[test]
+    fn test_unknown_and_truncated() {
+        assert_eq!(dimensions(b"P6\n37 23\n255\n"), None);
+        assert_eq!(dimensions(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0"), None);
+        assert_eq!(dimensions(b"\xff\xd8\xff\xe0\0\x10JFIF"), None);
+        assert_eq!(dimensions(b""), None);
+    }
+}
@@ -0,0 +1,225 @@
+//! Where image files are looked up, like Java's `ImagePath`
+//!
+//! Relative image names are searched in the bundle path (the script
+//! folder, the current directory by default) and then in the added
+//! directories, in the order they were added. A directory `foo` that does
+//! not exist falls back to the `foo.sikuli` folder of old SikuliX scripts.
+//! Names without an extension also match `.png`, `.jpg` and `.jpeg` files.
+
+use crate::{Error, Result};
+use std::path::{Path, PathBuf};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Extensions tried for image names that have none, in this order
+pub const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg"];
+
+/// Folder extension of SikuliX script bundles
+const BUNDLE_EXTENSION: &str = "sikuli";
+
+/// The registry used by `Image::resolve` and the vision crate
+static GLOBAL: RwLock<ImagePath> = RwLock::new(ImagePath::new());
+
+/// An ordered list of directories to look up image files in
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImagePath {
+    /// Searched first; the current directory if unset
+    bundle: Option<PathBuf>,
+
+    /// Searched after the bundle path, in order
+    paths: Vec<PathBuf>,
+}
+
+impl ImagePath {
+    /// Create a registry that only searches the current directory
+    pub const fn new() -> Self {
+        Self {
+            bundle: None,
+            paths: Vec::new(),
+        }
+    }
+
+    /// Get the process-wide registry
+    pub fn global() -> RwLockReadGuard<'static, ImagePath> {
+        // Every update leaves the registry consistent, even if interrupted
+        GLOBAL.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Get the process-wide registry for changing it
+    pub fn global_mut() -> RwLockWriteGuard<'static, ImagePath> {
+        GLOBAL.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Set the directory searched first, usually the script's folder
+    pub fn set_bundle_path(&mut self, dir: impl Into<PathBuf>) {
+        self.bundle = Some(dir.into());
+    }
+
+    /// Get the directory searched first, if one was set
+    pub fn bundle_path(&self) -> Option<&Path> {
+        self.bundle.as_deref()
+    }
+
+    /// Append `dir` to the searched directories, unless it is listed already
+    pub fn add(&mut self, dir: impl Into<PathBuf>) {
+        let dir = dir.into();
+        if !self.paths.contains(&dir) {
+            self.paths.push(dir);
+        }
+    }
+
+    /// Remove `dir` from the searched directories
+    ///
+    /// Returns whether it was listed.
+    pub fn remove(&mut self, dir: impl AsRef<Path>) -> bool {
+        let before = self.paths.len();
+        self.paths.retain(|p| p != dir.as_ref());
+        self.paths.len() != before
+    }
+
+    /// Forget the bundle path and all added directories
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Get the added directories, in search order
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Find the image file `name`
+    ///
+    /// Absolute names are only checked for the missing extensions.
+    ///
+    /// # Errors
+    /// Returns `Error::ImageNotFound` listing every location tried.
+    pub fn resolve(&self, name: impl AsRef<Path>) -> Result<PathBuf> {
+        let name = name.as_ref();
+        let candidates: Vec<PathBuf> = if name.is_absolute() {
+            with_extensions(name)
+        } else {
+            self.search_dirs()
+                .iter()
+                .flat_map(|dir| with_extensions(&dir.join(name)))
+                .collect()
+        };
+
+        if let Some(found) = candidates.iter().find(|path| path.is_file()) {
+            return Ok(found.clone());
+        }
+        let searched: Vec<String> = candidates
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        Err(Error::ImageNotFound(format!(
+            "{} (searched {})",
+            name.display(),
+            searched.join(", ")
+        )))
+    }
+
+    /// Get the directories searched for relative names, in order
+    pub fn search_dirs(&self) -> Vec<PathBuf> {
+        let bundle = self.bundle.clone().unwrap_or_else(|| PathBuf::from("."));
+        std::iter::once(bundle)
+            .chain(self.paths.iter().cloned())
+            .map(bundle_folder)
+            .collect()
+    }
+}
+
+/// Use `dir.sikuli` for a directory `dir` that does not exist
+fn bundle_folder(dir: PathBuf) -> PathBuf {
+    if dir.is_dir() || dir.extension().is_some_and(|ext| ext == BUNDLE_EXTENSION) {
+        return dir;
+    }
+    let mut bundle = dir.clone().into_os_string();
+    bundle.push(".");
+    bundle.push(BUNDLE_EXTENSION);
+    let bundle = PathBuf::from(bundle);
+    if bundle.is_dir() {
+        bundle
+    } else {
+        dir
+    }
+}
+
+/// `path` itself, then with each image extension if it has none
+fn with_extensions(path: &Path) -> Vec<PathBuf> {
+    let mut paths = vec![path.to_path_buf()];
+    if path.extension().is_none() {
+        paths.extend(IMAGE_EXTENSIONS.iter().map(|ext| path.with_extension(ext)));
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn touch(path: &Path) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, b"").unwrap();
+    }
+
+    #[test]
+    fn test_resolve_in_order() {
+        let dir = TempDir::new().unwrap();
+        let (bundle, first, second) = (
+            dir.path().join("bundle"),
+            dir.path().join("first"),
+            dir.path().join("second"),
+        );
+        touch(&first.join("ok.png"));
+        touch(&second.join("ok.png"));
+        touch(&second.join("logo.jpg"));
+        touch(&bundle.join("cancel.png"));
+
+        let mut path = ImagePath::new();
+        path.set_bundle_path(&bundle);
+        path.add(&first);
+        path.add(&second);
+        path.add(&first);
+        assert_eq!(path.paths(), &[first.clone(), second.clone()]);
+
+        assert_eq!(path.resolve("ok.png").unwrap(), first.join("ok.png"));
+        assert_eq!(path.resolve("ok").unwrap(), first.join("ok.png"));
+        assert_eq!(path.resolve("logo").unwrap(), second.join("logo.jpg"));
+        assert_eq!(path.resolve("cancel").unwrap(), bundle.join("cancel.png"));
+
+        assert!(path.remove(&first));
+        assert!(!path.remove(&first));
+        assert_eq!(path.resolve("ok").unwrap(), second.join("ok.png"));
+    }
+
+    #[test]
+    fn test_resolve_sikuli_folder() {
+        let dir = TempDir::new().unwrap();
+        touch(&dir.path().join("login.sikuli").join("user.png"));
+
+        let mut path = ImagePath::new();
+        path.add(dir.path().join("login"));
+        assert_eq!(
+            path.resolve("user").unwrap(),
+            dir.path().join("login.sikuli").join("user.png")
+        );
+    }
+
+    #[test]
+    fn test_not_found_lists_searched_locations() {
+        let dir = TempDir::new().unwrap();
+        let mut path = ImagePath::new();
+        path.set_bundle_path(dir.path());
+        path.add(dir.path().join("images"));
+
+        let Err(Error::ImageNotFound(message)) = path.resolve("missing") else {
+            panic!("missing image should not resolve");
+        };
+        for file in ["missing", "missing.png", "missing.jpg", "missing.jpeg"] {
+            assert!(message.contains(&dir.path().join(file).display().to_string()));
+            let in_images = dir.path().join("images").join(file);
+            assert!(message.contains(&in_images.display().to_string()));
+        }
+    }
+}
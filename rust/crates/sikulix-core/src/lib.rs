@@ -6,16 +6,41 @@
 //! - `Region`: A rectangular area of the screen
 //! - `Pattern`: An image pattern to search for
 //! - `Match`: The result of a successful pattern match
+//! - `Matches`: A sortable collection of matches
 //! - `Image`: Representation of an image
+//! - `ImagePath`: Directories image names are looked up in
+//! - `ScreenImage`: Captured screen pixels in BGR layout
+//! - `AutoRegion`: A region automated through pluggable backends
+//! - `VirtualDesktop`: An in-memory screen and input backend for tests
 
+pub mod automation;
+pub mod clock;
 pub mod error;
+pub mod find_failed;
 pub mod image;
+pub mod image_header;
+pub mod image_path;
 pub mod location;
+pub mod matches;
 pub mod pattern;
 pub mod region;
+pub mod screen_image;
+pub mod virtual_desktop;
 
+pub use automation::{
+    AutoRegion, Backends, FinderBackend, InputBackend, MouseButton, ScreenBackend,
+};
+pub use clock::{Clock, ManualClock, SystemClock};
 pub use error::{Error, Result};
+pub use find_failed::{
+    Evidence, EvidenceWriter, FindFailed, FindFailedAction, FindFailedHandler, FindFailedResponse,
+    FindFailure,
+};
 pub use image::Image;
+pub use image_path::ImagePath;
 pub use location::{Location, Offset};
-pub use pattern::{Match, Pattern};
+pub use matches::{MatchOrder, Matches};
+pub use pattern::{Match, MatchMethod, Pattern, PatternMask, ScaleSearch};
 pub use region::Region;
+pub use screen_image::ScreenImage;
+pub use virtual_desktop::{Element, InputEvent, SceneChange, Trigger, VirtualDesktop};
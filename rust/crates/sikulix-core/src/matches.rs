@@ -0,0 +1,169 @@
+//! Collections of pattern matches, as returned by find-all operations
+
+use crate::{Location, Match};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+/// Sort order for a collection of matches
+///
+/// Mirrors the comparators of the Java API (`byScore`, `byPosition`,
+/// `byDistance`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MatchOrder {
+    /// Highest score first
+    Score,
+
+    /// Row-major: top to bottom, then left to right (by top-left corner)
+    ReadingOrder,
+
+    /// Closest match center to the given location first
+    DistanceFrom(Location),
+}
+
+/// An ordered collection of matches
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Matches {
+    matches: Vec<Match>,
+}
+
+impl Matches {
+    /// Create a collection from a list of matches, keeping their order
+    pub fn new(matches: Vec<Match>) -> Self {
+        Self { matches }
+    }
+
+    /// Number of matches
+    pub fn len(&self) -> usize {
+        self.matches.len()
+    }
+
+    /// Check if there are no matches
+    pub fn is_empty(&self) -> bool {
+        self.matches.is_empty()
+    }
+
+    /// Iterate over the matches in their current order
+    pub fn iter(&self) -> std::slice::Iter<'_, Match> {
+        self.matches.iter()
+    }
+
+    /// Get the match at `index` in the current order
+    pub fn get(&self, index: usize) -> Option<&Match> {
+        self.matches.get(index)
+    }
+
+    /// Get the match with the highest score, regardless of the current order
+    pub fn best(&self) -> Option<&Match> {
+        self.matches.iter().max_by(|a, b| compare_score(b, a))
+    }
+
+    /// Sort the matches in place
+    pub fn sort_by(&mut self, order: MatchOrder) {
+        match order {
+            MatchOrder::Score => self.matches.sort_by(compare_score),
+            MatchOrder::ReadingOrder => self.matches.sort_by_key(|m| (m.region.y, m.region.x)),
+            MatchOrder::DistanceFrom(origin) => self.matches.sort_by(|a, b| {
+                origin
+                    .distance_to(a.center())
+                    .total_cmp(&origin.distance_to(b.center()))
+            }),
+        }
+    }
+
+    /// Return the matches sorted by `order`
+    pub fn sorted(mut self, order: MatchOrder) -> Self {
+        self.sort_by(order);
+        self
+    }
+
+    /// Borrow the matches as a slice
+    pub fn as_slice(&self) -> &[Match] {
+        &self.matches
+    }
+
+    /// Consume the collection, returning the underlying vector
+    pub fn into_vec(self) -> Vec<Match> {
+        self.matches
+    }
+}
+
+/// Descending by score; ties are broken by reading order so sorting is stable
+fn compare_score(a: &Match, b: &Match) -> Ordering {
+    b.score
+        .total_cmp(&a.score)
+        .then_with(|| (a.region.y, a.region.x).cmp(&(b.region.y, b.region.x)))
+}
+
+impl From<Vec<Match>> for Matches {
+    fn from(matches: Vec<Match>) -> Self {
+        Self::new(matches)
+    }
+}
+
+impl FromIterator<Match> for Matches {
+    fn from_iter<I: IntoIterator<Item = Match>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for Matches {
+    type Item = Match;
+    type IntoIter = std::vec::IntoIter<Match>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.matches.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Matches {
+    type Item = &'a Match;
+    type IntoIter = std::slice::Iter<'a, Match>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.matches.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Region;
+
+    fn sample() -> Matches {
+        Matches::new(vec![
+            Match::new(Region::new(100, 50, 10, 10), 0.80),
+            Match::new(Region::new(0, 50, 10, 10), 0.95),
+            Match::new(Region::new(50, 0, 10, 10), 0.90),
+        ])
+    }
+
+    fn xs(matches: &Matches) -> Vec<i32> {
+        matches.iter().map(|m| m.region.x).collect()
+    }
+
+    #[test]
+    fn test_sort_by_score() {
+        let matches = sample().sorted(MatchOrder::Score);
+        assert_eq!(xs(&matches), vec![0, 50, 100]);
+    }
+
+    #[test]
+    fn test_sort_by_reading_order() {
+        let matches = sample().sorted(MatchOrder::ReadingOrder);
+        assert_eq!(xs(&matches), vec![50, 0, 100]);
+    }
+
+    #[test]
+    fn test_sort_by_distance() {
+        let matches = sample().sorted(MatchOrder::DistanceFrom(Location::new(110, 60)));
+        assert_eq!(xs(&matches), vec![100, 50, 0]);
+    }
+
+    #[test]
+    fn test_best_and_collect() {
+        let matches: Matches = sample().into_iter().filter(|m| m.score < 0.95).collect();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches.best().unwrap().score, 0.90);
+        assert!(Matches::default().best().is_none());
+    }
+}
@@ -14,6 +14,18 @@ pub struct Pattern {
 
     /// Target offset from the match center
     pub target_offset: Offset,
+
+    /// Template scales to try, for captures taken at other display scalings
+    #[serde(default)]
+    pub scales: Option<ScaleSearch>,
+
+    /// Mask restricting which template pixels count towards the score
+    #[serde(default)]
+    pub mask: Option<PatternMask>,
+
+    /// Template matching algorithm used to score candidates
+    #[serde(default)]
+    pub method: MatchMethod,
 }
 
 impl Pattern {
@@ -23,6 +35,9 @@ impl Pattern {
             image,
             similarity: 0.7, // Default similarity threshold
             target_offset: Offset::zero(),
+            scales: None,
+            mask: None,
+            method: MatchMethod::default(),
         }
     }
 
@@ -38,12 +53,175 @@ impl Pattern {
         self
     }
 
+    /// Search the template at every scale from `min` to `max` in `step` increments
+    ///
+    /// A factor of 1.25 finds a pattern captured at 100% display scaling on
+    /// a screen running at 125%.
+    pub fn scale_range(mut self, min: f32, max: f32, step: f32) -> Self {
+        self.scales = Some(ScaleSearch::Range { min, max, step });
+        self
+    }
+
+    /// Search the template at an explicit list of scale factors
+    pub fn scale_factors(mut self, factors: impl Into<Vec<f32>>) -> Self {
+        self.scales = Some(ScaleSearch::Factors(factors.into()));
+        self
+    }
+
+    /// Ignore the transparent pixels of the pattern image when matching
+    ///
+    /// The pattern image must have an alpha channel (e.g. a PNG with
+    /// transparency).
+    pub fn mask_from_alpha(mut self) -> Self {
+        self.mask = Some(PatternMask::Alpha);
+        self
+    }
+
+    /// Ignore the template pixels that are black in a separate mask image
+    ///
+    /// The mask image must have the same dimensions as the pattern image.
+    pub fn mask(mut self, mask: impl Into<Image>) -> Self {
+        self.mask = Some(PatternMask::Image(mask.into()));
+        self
+    }
+
+    /// Set the template matching algorithm
+    pub fn method(mut self, method: MatchMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Get the scale factors to search, `[1.0]` when no scale search is set
+    pub fn search_scales(&self) -> Vec<f32> {
+        self.scales
+            .as_ref()
+            .map_or_else(|| vec![1.0], ScaleSearch::factors)
+    }
+
     /// Get the target location for a match
     pub fn get_target_location(&self, match_center: Location) -> Location {
         match_center.offset(self.target_offset)
     }
 }
 
+impl From<Image> for Pattern {
+    fn from(image: Image) -> Self {
+        Self::new(image)
+    }
+}
+
+impl From<&str> for Pattern {
+    fn from(path: &str) -> Self {
+        Self::new(Image::from(path))
+    }
+}
+
+impl From<String> for Pattern {
+    fn from(path: String) -> Self {
+        Self::new(Image::from(path))
+    }
+}
+
+/// Template matching algorithm, mirroring OpenCV's `TM_*` methods
+///
+/// Whatever the method, scores are reported in the 0.0 - 1.0 range of
+/// `Match::score`, where 1.0 is a perfect match. The normalised methods
+/// are independent of image brightness and should be preferred.
+/// Templates of a single plain color are always compared by squared
+/// difference, since correlation cannot tell two plain colors apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum MatchMethod {
+    /// Sum of squared differences (`TM_SQDIFF`)
+    SqDiff,
+
+    /// Normalised sum of squared differences (`TM_SQDIFF_NORMED`)
+    SqDiffNormed,
+
+    /// Cross correlation (`TM_CCORR`)
+    CCorr,
+
+    /// Normalised cross correlation (`TM_CCORR_NORMED`)
+    CCorrNormed,
+
+    /// Correlation coefficient (`TM_CCOEFF`)
+    CCoeff,
+
+    /// Normalised correlation coefficient (`TM_CCOEFF_NORMED`), as in the Java API
+    #[default]
+    CCoeffNormed,
+}
+
+impl MatchMethod {
+    /// Check if lower raw values mean better matches (the squared difference methods)
+    pub fn is_difference(self) -> bool {
+        matches!(self, MatchMethod::SqDiff | MatchMethod::SqDiffNormed)
+    }
+
+    /// Check if the raw values already lie in a fixed range
+    pub fn is_normed(self) -> bool {
+        matches!(
+            self,
+            MatchMethod::SqDiffNormed | MatchMethod::CCorrNormed | MatchMethod::CCoeffNormed
+        )
+    }
+}
+
+/// Where the matching mask of a pattern comes from
+///
+/// Masked-out pixels are ignored when computing the match score, so only
+/// the visible part of an icon has to match (e.g. a glyph on a gradient).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PatternMask {
+    /// The alpha channel of the pattern image; fully transparent pixels are ignored
+    Alpha,
+
+    /// A separate grayscale image; black pixels are ignored
+    Image(Image),
+}
+
+/// Template scale factors tried during a multi-scale search
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ScaleSearch {
+    /// Every factor from `min` to `max` (inclusive), `step` apart
+    Range { min: f32, max: f32, step: f32 },
+
+    /// An explicit list of factors
+    Factors(Vec<f32>),
+}
+
+impl ScaleSearch {
+    /// Upper bound on the number of factors a range expands to
+    const MAX_STEPS: usize = 256;
+
+    /// Expand to a sorted list of distinct, positive scale factors
+    ///
+    /// Invalid input (non-positive factors or step, `min > max`) is dropped,
+    /// falling back to `[1.0]` if nothing usable is left.
+    pub fn factors(&self) -> Vec<f32> {
+        let mut factors: Vec<f32> = match self {
+            ScaleSearch::Range { min, max, step } => {
+                if *step > 0.0 && min <= max {
+                    (0..Self::MAX_STEPS)
+                        .map(|i| min + step * i as f32)
+                        .take_while(|f| *f <= max + step * 1e-3)
+                        .collect()
+                } else {
+                    Vec::new()
+                }
+            }
+            ScaleSearch::Factors(list) => list.clone(),
+        };
+
+        factors.retain(|f| f.is_finite() && *f > 0.0);
+        factors.sort_by(f32::total_cmp);
+        factors.dedup_by(|a, b| (*a - *b).abs() < 1e-4);
+        if factors.is_empty() {
+            factors.push(1.0);
+        }
+        factors
+    }
+}
+
 /// The result of a successful pattern match
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Match {
@@ -55,6 +233,14 @@ pub struct Match {
 
     /// Target offset from the match center
     pub target_offset: Offset,
+
+    /// Scale factor of the template that produced this match
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+}
+
+fn default_scale() -> f32 {
+    1.0
 }
 
 impl Match {
@@ -64,6 +250,7 @@ impl Match {
             region,
             score,
             target_offset: Offset::zero(),
+            scale: 1.0,
         }
     }
 
@@ -73,6 +260,12 @@ impl Match {
         self
     }
 
+    /// Create a match with the template scale it was found at
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
     /// Get the center location of the match
     pub fn center(&self) -> Location {
         self.region.center()
@@ -109,6 +302,75 @@ mod tests {
         assert_eq!(pattern.similarity, 1.0);
     }
 
+    #[test]
+    fn test_scale_range_factors() {
+        let pattern = Pattern::new(Image::from_path("test.png")).scale_range(1.0, 1.5, 0.25);
+        assert_eq!(pattern.search_scales(), vec![1.0, 1.25, 1.5]);
+
+        let pattern = Pattern::new(Image::from_path("test.png"));
+        assert_eq!(pattern.search_scales(), vec![1.0]);
+    }
+
+    #[test]
+    fn test_scale_factors_cleanup() {
+        let search = ScaleSearch::Factors(vec![1.5, 0.0, 1.0, 1.25, 1.0, -2.0, f32::NAN]);
+        assert_eq!(search.factors(), vec![1.0, 1.25, 1.5]);
+
+        let invalid = ScaleSearch::Range {
+            min: 1.0,
+            max: 2.0,
+            step: 0.0,
+        };
+        assert_eq!(invalid.factors(), vec![1.0]);
+        let reversed = ScaleSearch::Range {
+            min: 2.0,
+            max: 1.0,
+            step: 0.1,
+        };
+        assert_eq!(reversed.factors(), vec![1.0]);
+        let no_step = ScaleSearch::Range {
+            min: 0.5,
+            max: 2.0,
+            step: -0.5,
+        };
+        assert_eq!(no_step.factors(), vec![1.0]);
+        assert_eq!(ScaleSearch::Factors(vec![]).factors(), vec![1.0]);
+    }
+
+    #[test]
+    fn test_pattern_mask_builders() {
+        let pattern = Pattern::new(Image::from_path("icon.png"));
+        assert!(pattern.mask.is_none());
+
+        let alpha = pattern.clone().mask_from_alpha();
+        assert!(matches!(alpha.mask, Some(PatternMask::Alpha)));
+
+        let separate = pattern.mask("icon_mask.png");
+        match separate.mask {
+            Some(PatternMask::Image(image)) => assert_eq!(image.path(), "icon_mask.png"),
+            other => panic!("unexpected mask: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_match_method() {
+        let pattern = Pattern::new(Image::from_path("panel.png"));
+        assert_eq!(pattern.method, MatchMethod::CCoeffNormed);
+
+        let pattern = pattern.method(MatchMethod::SqDiff);
+        assert_eq!(pattern.method, MatchMethod::SqDiff);
+        assert!(pattern.method.is_difference());
+        assert!(!pattern.method.is_normed());
+        assert!(MatchMethod::CCorrNormed.is_normed());
+    }
+
+    #[test]
+    fn test_match_scale_default() {
+        let m = Match::new(Region::new(0, 0, 10, 10), 0.9);
+        assert_eq!(m.scale, 1.0);
+        assert_eq!(m.with_scale(1.25).scale, 1.25);
+    }
+
     #[test]
     fn test_match_target() {
         let region = Region::new(100, 100, 50, 50);
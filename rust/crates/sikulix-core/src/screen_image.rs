@@ -0,0 +1,194 @@
+//! Captured screen pixels
+
+use crate::{Error, Region, Result};
+use std::fmt;
+
+/// A captured area of the screen, stored as packed 8-bit BGR pixels
+///
+/// Rows start `stride` bytes apart, which is at least `3 * width`. This is
+/// the layout of an OpenCV CV_8UC3 Mat, so the vision crate can wrap a
+/// capture without converting it.
+#[derive(Clone, PartialEq)]
+pub struct ScreenImage {
+    /// Screen area the pixels were captured from
+    region: Region,
+
+    /// Distance between the starts of two rows, in bytes
+    stride: usize,
+
+    /// Pixel data, `stride * height` bytes
+    data: Vec<u8>,
+}
+
+impl ScreenImage {
+    /// Number of bytes per pixel
+    pub const CHANNELS: usize = 3;
+
+    /// Create a black image covering `region`, with tightly packed rows
+    pub fn new(region: Region) -> Self {
+        let stride = Self::CHANNELS * region.w.max(0) as usize;
+        Self {
+            region,
+            stride,
+            data: vec![0; stride * region.h.max(0) as usize],
+        }
+    }
+
+    /// Wrap BGR pixel data captured from `region`
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidRegion` if the region has a negative size, and
+    /// `Error::InvalidParameter` if `stride` is shorter than a row or `data`
+    /// holds fewer than `stride * height` bytes.
+    pub fn from_bgr(region: Region, stride: usize, data: Vec<u8>) -> Result<Self> {
+        if region.w < 0 || region.h < 0 {
+            return Err(Error::InvalidRegion(format!(
+                "Negative image size {}x{}",
+                region.w, region.h
+            )));
+        }
+
+        let row_len = Self::CHANNELS * region.w as usize;
+        if stride < row_len {
+            return Err(Error::InvalidParameter(format!(
+                "Stride {} is shorter than a row of {} bytes",
+                stride, row_len
+            )));
+        }
+        let needed = stride * region.h as usize;
+        if data.len() < needed {
+            return Err(Error::InvalidParameter(format!(
+                "Expected {} bytes of pixel data, got {}",
+                needed,
+                data.len()
+            )));
+        }
+
+        Ok(Self {
+            region,
+            stride,
+            data,
+        })
+    }
+
+    /// Get the screen area the pixels were captured from
+    pub fn region(&self) -> Region {
+        self.region
+    }
+
+    /// Get the image width in pixels
+    pub fn width(&self) -> i32 {
+        self.region.w
+    }
+
+    /// Get the image height in pixels
+    pub fn height(&self) -> i32 {
+        self.region.h
+    }
+
+    /// Get the distance between the starts of two rows, in bytes
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Get the raw pixel data, including any row padding
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Get the raw pixel data mutably, including any row padding
+    pub fn data_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+
+    /// Get the BGR bytes of row `y`, without padding
+    ///
+    /// # Panics
+    /// Panics if `y` is outside the image.
+    pub fn row(&self, y: i32) -> &[u8] {
+        let range = self.row_range(y);
+        &self.data[range]
+    }
+
+    /// Get the BGR bytes of row `y` mutably, without padding
+    ///
+    /// # Panics
+    /// Panics if `y` is outside the image.
+    pub fn row_mut(&mut self, y: i32) -> &mut [u8] {
+        let range = self.row_range(y);
+        &mut self.data[range]
+    }
+
+    /// Get the `[b, g, r]` value of the pixel at (`x`, `y`) relative to the image
+    pub fn pixel(&self, x: i32, y: i32) -> Option<[u8; 3]> {
+        if x < 0 || y < 0 || x >= self.width() || y >= self.height() {
+            return None;
+        }
+        let start = Self::CHANNELS * x as usize;
+        let row = self.row(y);
+        Some([row[start], row[start + 1], row[start + 2]])
+    }
+
+    /// Consume the image, returning the raw pixel data
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+
+    fn row_range(&self, y: i32) -> std::ops::Range<usize> {
+        assert!(
+            y >= 0 && y < self.height(),
+            "row {} out of range 0..{}",
+            y,
+            self.height()
+        );
+        let start = y as usize * self.stride;
+        start..start + Self::CHANNELS * self.width() as usize
+    }
+}
+
+impl fmt::Debug for ScreenImage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScreenImage")
+            .field("region", &self.region)
+            .field("stride", &self.stride)
+            .field("bytes", &self.data.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_is_black_and_packed() {
+        let image = ScreenImage::new(Region::new(10, 20, 4, 3));
+        assert_eq!(image.stride(), 12);
+        assert_eq!(image.data().len(), 36);
+        assert_eq!(image.pixel(3, 2), Some([0, 0, 0]));
+        assert_eq!(image.pixel(4, 0), None);
+    }
+
+    #[test]
+    fn test_from_bgr_with_padding() {
+        // 2x2 image with 2 bytes of padding per row
+        let data = vec![1, 2, 3, 4, 5, 6, 0, 0, 7, 8, 9, 10, 11, 12, 0, 0];
+        let image = ScreenImage::from_bgr(Region::new(0, 0, 2, 2), 8, data).unwrap();
+
+        assert_eq!(image.row(1), &[7, 8, 9, 10, 11, 12]);
+        assert_eq!(image.pixel(1, 0), Some([4, 5, 6]));
+    }
+
+    #[test]
+    fn test_from_bgr_validates_layout() {
+        let region = Region::new(0, 0, 2, 2);
+        assert!(matches!(
+            ScreenImage::from_bgr(region, 5, vec![0; 12]),
+            Err(Error::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            ScreenImage::from_bgr(region, 6, vec![0; 11]),
+            Err(Error::InvalidParameter(_))
+        ));
+    }
+}
@@ -0,0 +1,398 @@
+//! In-memory desktop for testing automation without a display
+//!
+//! A [`VirtualDesktop`] is both a `ScreenBackend` and an `InputBackend`. Its
+//! screen shows a scene of named images placed on a plain background; its
+//! input side records every event and fires scripted reactions, such as
+//! "clicking the OK button hides the dialog".
+
+use crate::{
+    Error, InputBackend, Location, MouseButton, Region, Result, ScreenBackend, ScreenImage,
+};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// An input event received by a `VirtualDesktop`
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputEvent {
+    /// The pointer moved
+    Move(Location),
+    /// A button was pressed at a location
+    ButtonDown(MouseButton, Location),
+    /// A button was released at a location
+    ButtonUp(MouseButton, Location),
+    /// Text was typed
+    Type(String),
+}
+
+/// What makes a reaction fire
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trigger {
+    /// `button` is released with the pointer inside `region`
+    Click { region: Region, button: MouseButton },
+    /// Exactly this text is typed in one call
+    Typed(String),
+}
+
+/// A change of the scene made by a reaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SceneChange {
+    /// Make the named element visible
+    Show(String),
+    /// Hide the named element
+    Hide(String),
+}
+
+/// A named image in the scene of a `VirtualDesktop`
+#[derive(Debug, Clone)]
+pub struct Element {
+    /// Name used to show, hide or replace the element
+    pub name: String,
+
+    /// Pixels of the element; its region is where it is placed
+    pub image: ScreenImage,
+
+    /// Whether the element is drawn
+    pub visible: bool,
+}
+
+/// A scripted reaction to input
+#[derive(Debug, Clone)]
+struct Reaction {
+    trigger: Trigger,
+    changes: Vec<SceneChange>,
+}
+
+#[derive(Debug)]
+struct Scene {
+    background: [u8; 3],
+    elements: Vec<Element>,
+    reactions: Vec<Reaction>,
+    pointer: Location,
+    events: Vec<InputEvent>,
+}
+
+/// A fake desktop with a scriptable scene and recorded input
+///
+/// Elements are drawn in the order they were added, later ones on top.
+/// Share it between a `Backends` and the test through an `Arc`:
+/// `Backends::from_shared(desktop.clone(), desktop.clone(), finder)`.
+#[derive(Debug)]
+pub struct VirtualDesktop {
+    bounds: Region,
+    scene: Mutex<Scene>,
+}
+
+impl VirtualDesktop {
+    /// Create a black desktop of `width` x `height` pixels
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            bounds: Region::new(0, 0, width.max(0), height.max(0)),
+            scene: Mutex::new(Scene {
+                background: [0, 0, 0],
+                elements: Vec::new(),
+                reactions: Vec::new(),
+                pointer: Location::new(0, 0),
+                events: Vec::new(),
+            }),
+        }
+    }
+
+    /// Set the `[b, g, r]` color of the background
+    pub fn with_background(self, bgr: [u8; 3]) -> Self {
+        self.scene().background = bgr;
+        self
+    }
+
+    /// Place `image` with its top-left corner at `at` and show it
+    ///
+    /// An element with the same name is replaced, keeping its stacking
+    /// position. Returns the area covered by the element.
+    pub fn add(&self, name: impl Into<String>, at: Location, image: ScreenImage) -> Region {
+        self.insert(name.into(), at, image, true)
+    }
+
+    /// Place `image` like `add`, but hidden until shown by `show` or a reaction
+    pub fn add_hidden(&self, name: impl Into<String>, at: Location, image: ScreenImage) -> Region {
+        self.insert(name.into(), at, image, false)
+    }
+
+    /// Add a visible element filling `region` with the `[b, g, r]` color
+    pub fn fill(&self, name: impl Into<String>, region: Region, bgr: [u8; 3]) -> Region {
+        let mut image = ScreenImage::new(region);
+        for pixel in image.data_mut().chunks_exact_mut(ScreenImage::CHANNELS) {
+            pixel.copy_from_slice(&bgr);
+        }
+        self.insert(name.into(), region.top_left(), image, true)
+    }
+
+    /// Make the named element visible
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidParameter` if there is no such element.
+    pub fn show(&self, name: &str) -> Result<()> {
+        self.scene().set_visible(name, true)
+    }
+
+    /// Hide the named element
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidParameter` if there is no such element.
+    pub fn hide(&self, name: &str) -> Result<()> {
+        self.scene().set_visible(name, false)
+    }
+
+    /// Check whether the named element exists and is visible
+    pub fn is_visible(&self, name: &str) -> bool {
+        self.scene()
+            .elements
+            .iter()
+            .any(|e| e.name == name && e.visible)
+    }
+
+    /// Get the area covered by the named element
+    pub fn element_region(&self, name: &str) -> Option<Region> {
+        self.scene()
+            .elements
+            .iter()
+            .find(|e| e.name == name)
+            .map(|e| e.image.region())
+    }
+
+    /// Apply `changes` whenever `trigger` happens
+    pub fn react(&self, trigger: Trigger, changes: Vec<SceneChange>) {
+        self.scene().reactions.push(Reaction { trigger, changes });
+    }
+
+    /// Apply `changes` whenever the left button is clicked inside `region`
+    pub fn on_click(&self, region: Region, changes: Vec<SceneChange>) {
+        let trigger = Trigger::Click {
+            region,
+            button: MouseButton::Left,
+        };
+        self.react(trigger, changes);
+    }
+
+    /// Get the current pointer location
+    pub fn pointer(&self) -> Location {
+        self.scene().pointer
+    }
+
+    /// Get the input events received so far
+    pub fn events(&self) -> Vec<InputEvent> {
+        self.scene().events.clone()
+    }
+
+    /// Get and forget the input events received so far
+    pub fn take_events(&self) -> Vec<InputEvent> {
+        std::mem::take(&mut self.scene().events)
+    }
+
+    fn insert(&self, name: String, at: Location, image: ScreenImage, visible: bool) -> Region {
+        let region = Region::new(at.x, at.y, image.width(), image.height());
+        let image = ScreenImage::from_bgr(region, image.stride(), image.into_data())
+            .expect("moving an image keeps its layout valid");
+        let element = Element {
+            name,
+            image,
+            visible,
+        };
+
+        let mut scene = self.scene();
+        match scene.elements.iter_mut().find(|e| e.name == element.name) {
+            Some(existing) => *existing = element,
+            None => scene.elements.push(element),
+        }
+        region
+    }
+
+    fn scene(&self) -> MutexGuard<'_, Scene> {
+        // A panicking test leaves the scene itself consistent
+        self.scene.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Scene {
+    fn set_visible(&mut self, name: &str, visible: bool) -> Result<()> {
+        let element = self
+            .elements
+            .iter_mut()
+            .find(|e| e.name == name)
+            .ok_or_else(|| Error::InvalidParameter(format!("No element named {:?}", name)))?;
+        element.visible = visible;
+        Ok(())
+    }
+
+    /// Apply the reactions whose trigger matches `happened`
+    fn fire(&mut self, happened: impl Fn(&Trigger) -> bool) {
+        let changes: Vec<SceneChange> = self
+            .reactions
+            .iter()
+            .filter(|r| happened(&r.trigger))
+            .flat_map(|r| r.changes.iter().cloned())
+            .collect();
+        for change in changes {
+            let (name, visible) = match &change {
+                SceneChange::Show(name) => (name, true),
+                SceneChange::Hide(name) => (name, false),
+            };
+            // Reactions may name elements that were replaced or never added
+            let _ = self.set_visible(name, visible);
+        }
+    }
+}
+
+impl ScreenBackend for VirtualDesktop {
+    fn bounds(&self) -> Result<Region> {
+        Ok(self.bounds)
+    }
+
+    fn capture(&self, region: Region) -> Result<ScreenImage> {
+        let area = self.bounds.intersection(&region).ok_or_else(|| {
+            Error::InvalidRegion(format!(
+                "Capture region {:?} lies outside the screen",
+                region
+            ))
+        })?;
+
+        let scene = self.scene();
+        let mut image = ScreenImage::new(area);
+        for pixel in image.data_mut().chunks_exact_mut(ScreenImage::CHANNELS) {
+            pixel.copy_from_slice(&scene.background);
+        }
+        for element in scene.elements.iter().filter(|e| e.visible) {
+            draw(&mut image, &element.image);
+        }
+        Ok(image)
+    }
+}
+
+impl InputBackend for VirtualDesktop {
+    fn move_to(&self, location: Location) -> Result<()> {
+        let mut scene = self.scene();
+        scene.pointer = location;
+        scene.events.push(InputEvent::Move(location));
+        Ok(())
+    }
+
+    fn button_down(&self, button: MouseButton) -> Result<()> {
+        let mut scene = self.scene();
+        let at = scene.pointer;
+        scene.events.push(InputEvent::ButtonDown(button, at));
+        Ok(())
+    }
+
+    fn button_up(&self, button: MouseButton) -> Result<()> {
+        let mut scene = self.scene();
+        let at = scene.pointer;
+        scene.events.push(InputEvent::ButtonUp(button, at));
+        scene.fire(|trigger| match trigger {
+            Trigger::Click { region, button: b } => *b == button && region.contains(at),
+            Trigger::Typed(_) => false,
+        });
+        Ok(())
+    }
+
+    fn type_text(&self, text: &str) -> Result<()> {
+        let mut scene = self.scene();
+        scene.events.push(InputEvent::Type(text.to_string()));
+        scene.fire(|trigger| matches!(trigger, Trigger::Typed(t) if t == text));
+        Ok(())
+    }
+}
+
+/// Copy the part of `src` that overlaps `dst`, both placed by their regions
+fn draw(dst: &mut ScreenImage, src: &ScreenImage) {
+    let Some(overlap) = dst.region().intersection(&src.region()) else {
+        return;
+    };
+
+    let channels = ScreenImage::CHANNELS;
+    let len = channels * overlap.w as usize;
+    let dst_x = channels * (overlap.x - dst.region().x) as usize;
+    let src_x = channels * (overlap.x - src.region().x) as usize;
+    for y in overlap.y..overlap.y + overlap.h {
+        let from = &src.row(y - src.region().y)[src_x..src_x + len];
+        let dst_y = y - dst.region().y;
+        dst.row_mut(dst_y)[dst_x..dst_x + len].copy_from_slice(from);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: [u8; 3] = [255, 255, 255];
+    const RED: [u8; 3] = [0, 0, 255];
+
+    #[test]
+    fn test_capture_draws_visible_elements_in_order() {
+        let desktop = VirtualDesktop::new(100, 80).with_background([10, 20, 30]);
+        desktop.fill("dialog", Region::new(10, 10, 40, 30), WHITE);
+        desktop.fill("button", Region::new(20, 20, 10, 10), RED);
+        desktop.fill("hidden", Region::new(0, 0, 100, 80), WHITE);
+        desktop.hide("hidden").unwrap();
+
+        let image = desktop.capture(Region::new(15, 15, 20, 20)).unwrap();
+        assert_eq!(image.pixel(0, 0), Some(WHITE));
+        assert_eq!(image.pixel(5, 5), Some(RED));
+
+        let image = desktop.capture(Region::new(90, 70, 50, 50)).unwrap();
+        assert_eq!(image.region(), Region::new(90, 70, 10, 10));
+        assert_eq!(image.pixel(0, 0), Some([10, 20, 30]));
+    }
+
+    #[test]
+    fn test_click_reaction_swaps_elements() {
+        let desktop = VirtualDesktop::new(100, 80);
+        desktop.fill("ok", Region::new(20, 20, 10, 10), WHITE);
+        let done = ScreenImage::new(Region::new(0, 0, 10, 10));
+        desktop.add_hidden("done", Location::new(20, 20), done);
+        desktop.on_click(
+            Region::new(20, 20, 10, 10),
+            vec![
+                SceneChange::Hide("ok".to_string()),
+                SceneChange::Show("done".to_string()),
+            ],
+        );
+
+        // A right click or a click elsewhere does nothing
+        desktop.move_to(Location::new(25, 25)).unwrap();
+        desktop.click(MouseButton::Right).unwrap();
+        desktop.move_to(Location::new(5, 5)).unwrap();
+        desktop.click(MouseButton::Left).unwrap();
+        assert!(desktop.is_visible("ok"));
+
+        desktop.move_to(Location::new(25, 25)).unwrap();
+        desktop.click(MouseButton::Left).unwrap();
+        assert!(!desktop.is_visible("ok"));
+        assert!(desktop.is_visible("done"));
+    }
+
+    #[test]
+    fn test_records_input_events() {
+        let desktop = VirtualDesktop::new(100, 80);
+        desktop.fill("prompt", Region::new(0, 0, 10, 10), WHITE);
+        desktop.react(
+            Trigger::Typed("secret".to_string()),
+            vec![SceneChange::Hide("prompt".to_string())],
+        );
+
+        desktop
+            .drag_drop(Location::new(1, 2), Location::new(3, 4))
+            .unwrap();
+        desktop.type_text("secret").unwrap();
+
+        let at = Location::new;
+        assert_eq!(
+            desktop.take_events(),
+            vec![
+                InputEvent::Move(at(1, 2)),
+                InputEvent::ButtonDown(MouseButton::Left, at(1, 2)),
+                InputEvent::Move(at(3, 4)),
+                InputEvent::ButtonUp(MouseButton::Left, at(3, 4)),
+                InputEvent::Type("secret".to_string()),
+            ]
+        );
+        assert!(desktop.events().is_empty());
+        assert!(!desktop.is_visible("prompt"));
+    }
+}
@@ -0,0 +1,33 @@
+// Benchmarks for screen capture - need an X display, e.g. `xvfb-run cargo bench`
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use sikulix_core::Region;
+use sikulix_platform::Screen;
+
+fn bench_capture(c: &mut Criterion) {
+    if std::env::var_os("DISPLAY").is_none() {
+        eprintln!("DISPLAY is not set, skipping screen capture benchmarks");
+        return;
+    }
+
+    let screen = Screen::primary().expect("failed to open the display");
+    let origin = screen.bounds().top_left();
+    let region = Region::new(origin.x, origin.y, 640, 480);
+    let mut group = c.benchmark_group("capture_640x480");
+
+    group.bench_function("xgetimage", |b| {
+        b.iter(|| screen.capture(region).unwrap());
+    });
+
+    // A frame rate far beyond what capturing reaches disables the pacing,
+    // so this measures the cost of one shared memory frame
+    let mut stream = screen.capture_stream(region, 100_000.0).unwrap();
+    group.bench_function("xshm_stream", |b| {
+        b.iter(|| stream.next_frame().unwrap().sequence);
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_capture);
+criterion_main!(benches);
@@ -0,0 +1,72 @@
+//! Desktop backends for `sikulix_core::AutoRegion`
+
+use crate::keyboard::Keyboard;
+use crate::mouse::Mouse;
+use crate::screen::Screen;
+use sikulix_core::{
+    InputBackend, Location, MouseButton, Region, Result, ScreenBackend, ScreenImage,
+};
+
+impl ScreenBackend for Screen {
+    fn bounds(&self) -> Result<Region> {
+        Ok(Screen::bounds(self))
+    }
+
+    fn capture(&self, region: Region) -> Result<ScreenImage> {
+        Screen::capture(self, region)
+    }
+}
+
+/// The real mouse and keyboard of the desktop
+pub struct SystemInput {
+    mouse: Mouse,
+    keyboard: Keyboard,
+}
+
+impl SystemInput {
+    /// Connect to the desktop's mouse and keyboard
+    pub fn new() -> Result<Self> {
+        Ok(Self::with(Mouse::new()?, Keyboard::new()?))
+    }
+
+    /// Use a configured mouse and keyboard, e.g. with a custom animator
+    pub fn with(mouse: Mouse, keyboard: Keyboard) -> Self {
+        Self { mouse, keyboard }
+    }
+
+    /// Get the mouse
+    pub fn mouse(&self) -> &Mouse {
+        &self.mouse
+    }
+
+    /// Get the keyboard
+    pub fn keyboard(&self) -> &Keyboard {
+        &self.keyboard
+    }
+}
+
+impl InputBackend for SystemInput {
+    fn move_to(&self, location: Location) -> Result<()> {
+        self.mouse.move_to(location)
+    }
+
+    fn button_down(&self, button: MouseButton) -> Result<()> {
+        self.mouse.button_down(button)
+    }
+
+    fn button_up(&self, button: MouseButton) -> Result<()> {
+        self.mouse.button_up(button)
+    }
+
+    fn double_click(&self) -> Result<()> {
+        self.mouse.double_click()
+    }
+
+    fn drag_drop(&self, from: Location, to: Location) -> Result<()> {
+        self.mouse.drag_drop(from, to)
+    }
+
+    fn type_text(&self, text: &str) -> Result<()> {
+        self.keyboard.type_text(text)
+    }
+}
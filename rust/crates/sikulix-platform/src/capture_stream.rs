@@ -0,0 +1,287 @@
+//! Continuous screen capture at a fixed frame rate
+
+use sikulix_core::{Error, Region, Result, ScreenImage};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::trace;
+
+#[cfg(target_os = "linux")]
+use crate::platform::linux::ShmCapture;
+
+/// One captured frame of a `CaptureStream`
+///
+/// Borrows the stream's frame buffer, which is overwritten by the next
+/// frame; copy the image if it has to outlive that.
+#[derive(Debug)]
+pub struct Frame<'a> {
+    /// The captured pixels
+    pub image: &'a ScreenImage,
+
+    /// When the capture completed
+    pub timestamp: Instant,
+
+    /// Position of the frame in the stream, counting dropped frames
+    pub sequence: u64,
+}
+
+/// Frame counters of a `CaptureStream`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CaptureStats {
+    /// Frames captured
+    pub frames: u64,
+
+    /// Frame slots skipped because the consumer or the capture fell behind
+    pub dropped: u64,
+
+    /// Time since the first frame was captured
+    pub elapsed: Duration,
+}
+
+impl CaptureStats {
+    /// Average rate of captured frames per second
+    pub fn fps(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            self.frames as f64 / secs
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Repeated captures of one screen area, paced to a target frame rate
+///
+/// On Linux the frames are grabbed through a MIT-SHM segment and converted
+/// into a single reused buffer, so steady-state capturing does not
+/// allocate. Frames are pulled with [`CaptureStream::next_frame`]; when a
+/// frame is requested late, the missed frame slots are counted as dropped
+/// instead of being captured in a burst. The shared memory is released
+/// when the stream is dropped.
+pub struct CaptureStream {
+    /// Shared memory grabber of the captured area
+    #[cfg(target_os = "linux")]
+    source: ShmCapture,
+
+    /// Frame buffer handed out by `next_frame`
+    buffer: ScreenImage,
+
+    /// Frame slot scheduling
+    pacer: FramePacer,
+
+    /// Capture time of the first frame
+    started: Option<Instant>,
+
+    /// Frames captured
+    frames: u64,
+}
+
+impl CaptureStream {
+    /// Start capturing `region` (clipped to the screen) at `fps` frames per second
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidParameter` if `fps` is not a positive number,
+    /// and platform errors if shared memory capture is unavailable.
+    #[cfg(target_os = "linux")]
+    pub fn new(region: Region, fps: f64) -> Result<Self> {
+        let interval = frame_interval(fps)?;
+        let source = ShmCapture::new(region)?;
+        Ok(Self {
+            buffer: ScreenImage::new(source.area()),
+            source,
+            pacer: FramePacer::new(interval),
+            started: None,
+            frames: 0,
+        })
+    }
+
+    /// Start capturing `region` at `fps` frames per second
+    #[cfg(not(target_os = "linux"))]
+    pub fn new(_region: Region, fps: f64) -> Result<Self> {
+        frame_interval(fps)?;
+        Err(unsupported())
+    }
+
+    /// Get the captured screen area
+    pub fn region(&self) -> Region {
+        self.buffer.region()
+    }
+
+    /// Get the target time between two frames
+    pub fn interval(&self) -> Duration {
+        self.pacer.interval
+    }
+
+    /// Wait for the next frame slot and capture it
+    pub fn next_frame(&mut self) -> Result<Frame<'_>> {
+        let (wait, missed) = self.pacer.schedule(Instant::now());
+        if !wait.is_zero() {
+            thread::sleep(wait);
+        }
+
+        self.grab()?;
+        let timestamp = Instant::now();
+        self.started.get_or_insert(timestamp);
+        self.frames += 1;
+
+        let sequence = self.frames - 1 + self.pacer.dropped;
+        if missed > 0 {
+            trace!("Dropped {} frames before frame {}", missed, sequence);
+        }
+        Ok(Frame {
+            image: &self.buffer,
+            timestamp,
+            sequence,
+        })
+    }
+
+    /// Get the frame counters so far
+    pub fn stats(&self) -> CaptureStats {
+        CaptureStats {
+            frames: self.frames,
+            dropped: self.pacer.dropped,
+            elapsed: self.started.map(|s| s.elapsed()).unwrap_or_default(),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn grab(&mut self) -> Result<()> {
+        self.source.grab()?;
+        self.source.copy_into(&mut self.buffer)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn grab(&mut self) -> Result<()> {
+        Err(unsupported())
+    }
+}
+
+/// Error of capture streams on platforms without a capture backend yet
+#[cfg(not(target_os = "linux"))]
+fn unsupported() -> Error {
+    Error::Platform("Capture streams are not supported on this platform".to_string())
+}
+
+/// Convert a frame rate into the time between two frames
+fn frame_interval(fps: f64) -> Result<Duration> {
+    if !fps.is_finite() || fps <= 0.0 {
+        return Err(Error::InvalidParameter(format!(
+            "Frame rate must be positive, got {}",
+            fps
+        )));
+    }
+    Ok(Duration::from_secs_f64(1.0 / fps))
+}
+
+/// Schedules frames on a fixed grid of time slots
+#[derive(Debug, Clone)]
+struct FramePacer {
+    /// Time between two slots
+    interval: Duration,
+
+    /// Start of the next slot, unset before the first frame
+    next: Option<Instant>,
+
+    /// Slots that passed without a frame
+    dropped: u64,
+}
+
+impl FramePacer {
+    fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            next: None,
+            dropped: 0,
+        }
+    }
+
+    /// Claim the next slot at time `now`
+    ///
+    /// Returns how long to wait for the slot to start, and how many slots
+    /// were missed since the previous frame.
+    fn schedule(&mut self, now: Instant) -> (Duration, u64) {
+        let Some(next) = self.next else {
+            self.next = Some(now + self.interval);
+            return (Duration::ZERO, 0);
+        };
+
+        if now <= next {
+            self.next = Some(next + self.interval);
+            return (next - now, 0);
+        }
+
+        // Skip ahead to the slot `now` falls into
+        let missed = ((now - next).as_nanos() / self.interval.as_nanos().max(1)) as u64;
+        let current = next + Duration::from_nanos(self.interval.as_nanos() as u64 * missed);
+        self.next = Some(current + self.interval);
+        self.dropped += missed;
+        (Duration::ZERO, missed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn test_pacer_waits_for_slot() {
+        let start = Instant::now();
+        let mut pacer = FramePacer::new(10 * MS);
+
+        assert_eq!(pacer.schedule(start), (Duration::ZERO, 0));
+        assert_eq!(pacer.schedule(start + 4 * MS), (6 * MS, 0));
+        // Second slot starts at 20ms
+        assert_eq!(pacer.schedule(start + 12 * MS), (8 * MS, 0));
+        assert_eq!(pacer.dropped, 0);
+    }
+
+    #[test]
+    fn test_pacer_counts_missed_slots() {
+        let start = Instant::now();
+        let mut pacer = FramePacer::new(10 * MS);
+        pacer.schedule(start);
+
+        // At 43ms the slots at 10, 20 and 30ms were missed; the frame takes
+        // the slot at 40ms and the next one starts at 50ms
+        assert_eq!(pacer.schedule(start + 43 * MS), (Duration::ZERO, 3));
+        assert_eq!(pacer.schedule(start + 45 * MS), (5 * MS, 0));
+        assert_eq!(pacer.dropped, 3);
+    }
+
+    #[test]
+    fn test_invalid_frame_rate() {
+        assert!(matches!(
+            frame_interval(0.0),
+            Err(Error::InvalidParameter(_))
+        ));
+        assert!(frame_interval(f64::NAN).is_err());
+        assert_eq!(frame_interval(50.0).unwrap(), 20 * MS);
+    }
+
+    #[test]
+    fn test_stats_fps() {
+        let stats = CaptureStats {
+            frames: 30,
+            dropped: 0,
+            elapsed: Duration::from_millis(500),
+        };
+        assert_eq!(stats.fps(), 60.0);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_stream_frames() {
+        if std::env::var_os("DISPLAY").is_none() {
+            return;
+        }
+        let mut stream = CaptureStream::new(Region::new(0, 0, 64, 48), 100.0).unwrap();
+
+        let first = stream.next_frame().unwrap().timestamp;
+        let second = stream.next_frame().unwrap();
+        assert_eq!(second.image.region(), Region::new(0, 0, 64, 48));
+        assert!(second.timestamp > first);
+        assert!(second.sequence >= 1);
+        assert_eq!(stream.stats().frames, 2);
+    }
+}
@@ -0,0 +1,330 @@
+//! Key constants, modifiers and hotkey chords
+
+use bitflags::bitflags;
+use sikulix_core::{Error, Result};
+use std::fmt;
+use std::str::FromStr;
+
+/// A key of the keyboard, like the constants of Java's `Key`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    /// The key that types a character, e.g. `t` in Ctrl+T
+    Char(char),
+
+    // Modifiers
+    Shift,
+    Ctrl,
+    Alt,
+    /// Windows / Super / Command key
+    Meta,
+    AltGr,
+
+    // Editing and navigation
+    Enter,
+    Tab,
+    Escape,
+    Backspace,
+    Delete,
+    Insert,
+    Space,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Up,
+    Down,
+    Left,
+    Right,
+
+    // Locks and system keys
+    CapsLock,
+    NumLock,
+    ScrollLock,
+    PrintScreen,
+    Pause,
+
+    // Function keys
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    F13,
+    F14,
+    F15,
+
+    // Keypad
+    Num0,
+    Num1,
+    Num2,
+    Num3,
+    Num4,
+    Num5,
+    Num6,
+    Num7,
+    Num8,
+    Num9,
+    NumAdd,
+    NumSubtract,
+    NumMultiply,
+    NumDivide,
+    NumDecimal,
+    NumEnter,
+}
+
+/// Names of the non-character keys, the canonical (lower case) name first
+const KEY_NAMES: &[(&str, Key)] = &[
+    ("shift", Key::Shift),
+    ("ctrl", Key::Ctrl),
+    ("control", Key::Ctrl),
+    ("alt", Key::Alt),
+    ("meta", Key::Meta),
+    ("win", Key::Meta),
+    ("super", Key::Meta),
+    ("cmd", Key::Meta),
+    ("altgr", Key::AltGr),
+    ("enter", Key::Enter),
+    ("return", Key::Enter),
+    ("tab", Key::Tab),
+    ("esc", Key::Escape),
+    ("escape", Key::Escape),
+    ("backspace", Key::Backspace),
+    ("delete", Key::Delete),
+    ("del", Key::Delete),
+    ("insert", Key::Insert),
+    ("ins", Key::Insert),
+    ("space", Key::Space),
+    ("home", Key::Home),
+    ("end", Key::End),
+    ("pageup", Key::PageUp),
+    ("pgup", Key::PageUp),
+    ("pagedown", Key::PageDown),
+    ("pgdn", Key::PageDown),
+    ("up", Key::Up),
+    ("down", Key::Down),
+    ("left", Key::Left),
+    ("right", Key::Right),
+    ("capslock", Key::CapsLock),
+    ("numlock", Key::NumLock),
+    ("scrolllock", Key::ScrollLock),
+    ("printscreen", Key::PrintScreen),
+    ("print", Key::PrintScreen),
+    ("pause", Key::Pause),
+    ("f1", Key::F1),
+    ("f2", Key::F2),
+    ("f3", Key::F3),
+    ("f4", Key::F4),
+    ("f5", Key::F5),
+    ("f6", Key::F6),
+    ("f7", Key::F7),
+    ("f8", Key::F8),
+    ("f9", Key::F9),
+    ("f10", Key::F10),
+    ("f11", Key::F11),
+    ("f12", Key::F12),
+    ("f13", Key::F13),
+    ("f14", Key::F14),
+    ("f15", Key::F15),
+    ("num0", Key::Num0),
+    ("num1", Key::Num1),
+    ("num2", Key::Num2),
+    ("num3", Key::Num3),
+    ("num4", Key::Num4),
+    ("num5", Key::Num5),
+    ("num6", Key::Num6),
+    ("num7", Key::Num7),
+    ("num8", Key::Num8),
+    ("num9", Key::Num9),
+    ("numadd", Key::NumAdd),
+    ("numsubtract", Key::NumSubtract),
+    ("nummultiply", Key::NumMultiply),
+    ("numdivide", Key::NumDivide),
+    ("numdecimal", Key::NumDecimal),
+    ("numenter", Key::NumEnter),
+];
+
+impl Key {
+    /// Look up a key by name, ignoring case
+    ///
+    /// A single character names the key that types it.
+    pub fn from_name(name: &str) -> Option<Key> {
+        let mut chars = name.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Some(Key::Char(c));
+        }
+
+        let name = name.to_ascii_lowercase();
+        KEY_NAMES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|&(_, key)| key)
+    }
+
+    /// Get the modifier this key applies, if it is a modifier key
+    pub fn modifier(self) -> Option<Modifiers> {
+        match self {
+            Key::Shift => Some(Modifiers::SHIFT),
+            Key::Ctrl => Some(Modifiers::CTRL),
+            Key::Alt => Some(Modifiers::ALT),
+            Key::Meta => Some(Modifiers::META),
+            Key::AltGr => Some(Modifiers::ALTGR),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Key::Char(c) = self {
+            return write!(f, "{}", c);
+        }
+        let (name, _) = KEY_NAMES
+            .iter()
+            .find(|(_, key)| key == self)
+            .expect("every named key is listed in KEY_NAMES");
+        f.write_str(name)
+    }
+}
+
+bitflags! {
+    /// Modifier keys held during a key press, like Java's `KeyModifier`
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    pub struct Modifiers: u8 {
+        const SHIFT = 1;
+        const CTRL = 1 << 1;
+        const ALT = 1 << 2;
+        /// Windows / Super / Command key
+        const META = 1 << 3;
+        const ALTGR = 1 << 4;
+    }
+}
+
+impl Modifiers {
+    /// Get the keys that hold these modifiers, in the order they are pressed
+    pub fn keys(self) -> impl Iterator<Item = Key> {
+        [Key::Ctrl, Key::Alt, Key::Shift, Key::Meta, Key::AltGr]
+            .into_iter()
+            .filter(move |key| key.modifier().is_some_and(|m| self.contains(m)))
+    }
+}
+
+/// A key pressed while holding modifiers, e.g. Ctrl+Shift+T
+///
+/// Parsed from strings like `"ctrl+shift+t"` or `"Alt+F4"`; a literal plus
+/// key is written `"ctrl++"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Chord {
+    /// Modifiers held while pressing the key
+    pub modifiers: Modifiers,
+
+    /// Key pressed last
+    pub key: Key,
+}
+
+impl Chord {
+    /// Create a chord pressing `key` while holding `modifiers`
+    pub fn new(modifiers: Modifiers, key: Key) -> Self {
+        Self { modifiers, key }
+    }
+}
+
+impl From<Key> for Chord {
+    fn from(key: Key) -> Self {
+        Self::new(Modifiers::empty(), key)
+    }
+}
+
+impl FromStr for Chord {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (prefix, key_name) = if s == "+" {
+            ("", "+")
+        } else if let Some(prefix) = s.strip_suffix("++") {
+            (prefix, "+")
+        } else {
+            s.rsplit_once('+').unwrap_or(("", s))
+        };
+
+        let key_name = key_name.trim();
+        let key = Key::from_name(key_name).ok_or_else(|| {
+            Error::InvalidParameter(format!("Unknown key {:?} in chord {:?}", key_name, s))
+        })?;
+
+        let mut modifiers = Modifiers::empty();
+        if !prefix.is_empty() {
+            for name in prefix.split('+').map(str::trim) {
+                let modifier = Key::from_name(name)
+                    .and_then(Key::modifier)
+                    .ok_or_else(|| {
+                        Error::InvalidParameter(format!(
+                            "{:?} in chord {:?} is not a modifier",
+                            name, s
+                        ))
+                    })?;
+                modifiers |= modifier;
+            }
+        }
+        Ok(Self { modifiers, key })
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for key in self.modifiers.keys() {
+            write!(f, "{}+", key)?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_chords() {
+        let chord: Chord = "ctrl+shift+t".parse().unwrap();
+        assert_eq!(
+            chord,
+            Chord::new(Modifiers::CTRL | Modifiers::SHIFT, Key::Char('t'))
+        );
+
+        let chord: Chord = "Alt + F4".parse().unwrap();
+        assert_eq!(chord, Chord::new(Modifiers::ALT, Key::F4));
+
+        let chord: Chord = "ctrl++".parse().unwrap();
+        assert_eq!(chord, Chord::new(Modifiers::CTRL, Key::Char('+')));
+
+        assert_eq!("esc".parse::<Chord>().unwrap(), Chord::from(Key::Escape));
+    }
+
+    #[test]
+    fn test_parse_invalid_chords() {
+        for chord in ["", "ctrl+", "ctrl+nokey", "t+ctrl", "ctrl++shift+t"] {
+            assert!(
+                matches!(chord.parse::<Chord>(), Err(Error::InvalidParameter(_))),
+                "{:?} should not parse",
+                chord
+            );
+        }
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        let chord = Chord::new(
+            Modifiers::SHIFT | Modifiers::CTRL | Modifiers::META,
+            Key::PageUp,
+        );
+        assert_eq!(chord.to_string(), "ctrl+shift+meta+pageup");
+        assert_eq!(chord.to_string().parse::<Chord>().unwrap(), chord);
+    }
+}
@@ -1,18 +1,371 @@
 //! Keyboard control
 
-use sikulix_core::Result;
+use crate::key::{Chord, Key, Modifiers};
+use sikulix_core::{Error, Result};
+use std::thread;
+use std::time::Duration;
+use tracing::warn;
+
+#[cfg(target_os = "linux")]
+use crate::platform::linux::{char_to_keysym, key_to_keysym, KeyStroke, XDisplay};
+#[cfg(target_os = "linux")]
+use std::sync::{Mutex, MutexGuard, PoisonError};
+#[cfg(target_os = "linux")]
+use x11::xlib::KeyCode;
+
+/// Time a temporarily remapped key keeps its keysym after being typed
+///
+/// Clients read the new mapping only after the server announced it, so
+/// restoring the key right away would make them see the old, empty one.
+#[cfg(target_os = "linux")]
+const REMAP_DELAY: Duration = Duration::from_millis(20);
 
 /// Keyboard control interface
+///
+/// On Linux keys are faked through the XTest extension of the X display
+/// named by `DISPLAY`. Text is typed with the keys of the active XKB
+/// layout; characters the layout lacks are typed by briefly mapping them
+/// onto an unused keycode.
 pub struct Keyboard {
-    // Will be implemented in Phase 2
+    /// Connection used to fake input, one caller at a time
+    #[cfg(target_os = "linux")]
+    display: Mutex<XDisplay>,
+
+    /// Keys held down on a spare keycode, because the layout lacks them
+    #[cfg(target_os = "linux")]
+    remapped: Mutex<Vec<(Key, KeyCode)>>,
+
+    /// Pause between two typed characters
+    char_delay: Duration,
 }
 
 impl Keyboard {
+    /// Connect to the display
+    ///
+    /// # Errors
+    /// Returns `Error::Platform` if the display cannot be opened or does
+    /// not support faking input.
+    #[cfg(target_os = "linux")]
     pub fn new() -> Result<Self> {
-        Ok(Self {})
+        let display = XDisplay::open()?;
+        display.require_xtest()?;
+        Ok(Self {
+            display: Mutex::new(display),
+            remapped: Mutex::new(Vec::new()),
+            char_delay: Duration::ZERO,
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            char_delay: Duration::ZERO,
+        })
+    }
+
+    /// Set the pause between two typed characters (Java's `TypeDelay`)
+    pub fn with_char_delay(mut self, delay: Duration) -> Self {
+        self.char_delay = delay;
+        self
+    }
+
+    /// Get the pause between two typed characters
+    pub fn char_delay(&self) -> Duration {
+        self.char_delay
+    }
+
+    /// Type `text` character by character
+    ///
+    /// `\n` presses Return and `\t` presses Tab.
+    pub fn type_text(&self, text: &str) -> Result<()> {
+        for (i, c) in text.chars().enumerate() {
+            if i > 0 && !self.char_delay.is_zero() {
+                thread::sleep(self.char_delay);
+            }
+            self.type_char(c)?;
+        }
+        Ok(())
+    }
+
+    /// Type a single character
+    #[cfg(target_os = "linux")]
+    pub fn type_char(&self, c: char) -> Result<()> {
+        let keysym = char_to_keysym(c);
+        let keycode = {
+            let display = self.display();
+            if let Some(stroke) = display.find_key(keysym) {
+                return display.fake_stroke(&stroke);
+            }
+
+            let keycode = display.spare_keycode().ok_or_else(|| {
+                Error::Platform(format!(
+                    "Cannot type {:?}: not in the keyboard layout and no spare keycode",
+                    c
+                ))
+            })?;
+            display.remap_keycode(keycode, keysym)?;
+            let typed = display.fake_stroke(&KeyStroke {
+                keycode,
+                modifiers: Vec::new(),
+            });
+            if typed.is_err() {
+                let _ = display.remap_keycode(keycode, 0);
+                return typed;
+            }
+            keycode
+        };
+
+        // Other input may use the display while the mapping settles
+        thread::sleep(REMAP_DELAY);
+        self.display().remap_keycode(keycode, 0)
     }
 
-    pub fn type_text(&self, _text: &str) -> Result<()> {
-        unimplemented!("Keyboard control - Phase 2")
+    #[cfg(not(target_os = "linux"))]
+    pub fn type_char(&self, _c: char) -> Result<()> {
+        Err(unsupported())
+    }
+
+    /// Press `key` without releasing it
+    ///
+    /// Characters on a shifted or AltGr level are pressed together with
+    /// that level's modifiers; characters the layout lacks are mapped onto
+    /// a spare keycode until they are released.
+    ///
+    /// Prefer [`Keyboard::hold`], which releases the key again even if
+    /// the caller fails or panics.
+    #[cfg(target_os = "linux")]
+    pub fn key_down(&self, key: Key) -> Result<()> {
+        let display = self.display();
+        let stroke = self.stroke(&display, key)?;
+        let mut pressed: Vec<KeyCode> = Vec::with_capacity(stroke.modifiers.len() + 1);
+        for &keycode in stroke.modifiers.iter().chain([&stroke.keycode]) {
+            if let Err(e) = display.fake_key(keycode, true) {
+                for &keycode in pressed.iter().rev() {
+                    let _ = display.fake_key(keycode, false);
+                }
+                return Err(e);
+            }
+            pressed.push(keycode);
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn key_down(&self, _key: Key) -> Result<()> {
+        Err(unsupported())
+    }
+
+    /// Release `key` and the level modifiers pressed with it
+    #[cfg(target_os = "linux")]
+    pub fn key_up(&self, key: Key) -> Result<()> {
+        let mut remapped = self.remapped();
+        let spare = remapped
+            .iter()
+            .position(|&(k, _)| k == key)
+            .map(|i| remapped.swap_remove(i).1);
+        drop(remapped);
+
+        let mut released = {
+            let display = self.display();
+            let stroke = match spare {
+                Some(keycode) => KeyStroke {
+                    keycode,
+                    modifiers: Vec::new(),
+                },
+                None => display
+                    .find_key(key_to_keysym(key))
+                    .ok_or_else(|| not_on_keyboard(key))?,
+            };
+            let mut released = display.fake_key(stroke.keycode, false);
+            for &keycode in stroke.modifiers.iter().rev() {
+                released = released.and(display.fake_key(keycode, false));
+            }
+            released
+        };
+
+        if let Some(keycode) = spare {
+            // Other input may use the display while the release settles
+            thread::sleep(REMAP_DELAY);
+            released = released.and(self.display().remap_keycode(keycode, 0));
+        }
+        released
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn key_up(&self, _key: Key) -> Result<()> {
+        Err(unsupported())
+    }
+
+    /// Hold `modifiers` down until the returned guard is released or dropped
+    pub fn hold(&self, modifiers: Modifiers) -> Result<HeldKeys<'_>> {
+        let mut held = HeldKeys {
+            keyboard: self,
+            keys: Vec::new(),
+        };
+        for key in modifiers.keys() {
+            held.down(key)?;
+        }
+        Ok(held)
+    }
+
+    /// Press and release the key of `chord` while holding its modifiers
+    ///
+    /// All keys are released again, also when pressing one of them fails.
+    pub fn press(&self, chord: Chord) -> Result<()> {
+        let mut held = self.hold(chord.modifiers)?;
+        held.down(chord.key)?;
+        held.release()
+    }
+
+    /// Find how to press `key`, mapping a character the layout lacks onto
+    /// a spare keycode like `type_char` does
+    #[cfg(target_os = "linux")]
+    fn stroke(&self, display: &XDisplay, key: Key) -> Result<KeyStroke> {
+        let keysym = key_to_keysym(key);
+        if let Some(stroke) = display.find_key(keysym) {
+            return Ok(stroke);
+        }
+        let Key::Char(c) = key else {
+            return Err(not_on_keyboard(key));
+        };
+
+        let keycode = display.spare_keycode().ok_or_else(|| {
+            Error::Platform(format!(
+                "Cannot press {:?}: not in the keyboard layout and no spare keycode",
+                c
+            ))
+        })?;
+        display.remap_keycode(keycode, keysym)?;
+        self.remapped().push((key, keycode));
+        Ok(KeyStroke {
+            keycode,
+            modifiers: Vec::new(),
+        })
+    }
+
+    #[cfg(target_os = "linux")]
+    fn display(&self) -> MutexGuard<'_, XDisplay> {
+        // A panic while faking input leaves the connection itself usable
+        self.display.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn remapped(&self) -> MutexGuard<'_, Vec<(Key, KeyCode)>> {
+        self.remapped.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Keys held down by a `Keyboard`
+///
+/// The keys are released in reverse order by [`HeldKeys::release`], or
+/// when the guard is dropped, which includes unwinding from a panic.
+#[must_use = "the keys are released as soon as the guard is dropped"]
+pub struct HeldKeys<'a> {
+    keyboard: &'a Keyboard,
+
+    /// Keys pressed so far, in press order
+    keys: Vec<Key>,
+}
+
+impl HeldKeys<'_> {
+    /// Press another key and hold it with the others
+    pub fn down(&mut self, key: Key) -> Result<()> {
+        self.keyboard.key_down(key)?;
+        self.keys.push(key);
+        Ok(())
+    }
+
+    /// Get the keys being held, in press order
+    pub fn keys(&self) -> &[Key] {
+        &self.keys
+    }
+
+    /// Release all held keys, reporting the first failure
+    pub fn release(mut self) -> Result<()> {
+        let mut result = Ok(());
+        while let Some(key) = self.keys.pop() {
+            result = result.and(self.keyboard.key_up(key));
+        }
+        result
+    }
+}
+
+impl Drop for HeldKeys<'_> {
+    fn drop(&mut self) {
+        while let Some(key) = self.keys.pop() {
+            if let Err(e) = self.keyboard.key_up(key) {
+                warn!("Failed to release {}: {}", key, e);
+            }
+        }
+    }
+}
+
+/// Error of the keyboard actions on platforms without an input backend yet
+#[cfg(not(target_os = "linux"))]
+fn unsupported() -> Error {
+    Error::Platform("Keyboard control is not supported on this platform".to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn not_on_keyboard(key: Key) -> Error {
+    Error::Platform(format!("Key {} is not on the keyboard", key))
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_type_text_outside_layout() {
+        if std::env::var_os("DISPLAY").is_none() {
+            return;
+        }
+        let keyboard = Keyboard::new()
+            .unwrap()
+            .with_char_delay(Duration::from_millis(1));
+        keyboard.type_text("aZ\täö€漢😀\n").unwrap();
+
+        // Remapped keys are released again
+        let display = keyboard.display();
+        let spare = display.spare_keycode();
+        assert!(spare.is_some());
+        assert!(display.find_key(char_to_keysym('😀')).is_none());
+    }
+
+    #[test]
+    fn test_press_shifted_and_missing_chars() {
+        if std::env::var_os("DISPLAY").is_none() {
+            return;
+        }
+        let keyboard = Keyboard::new().unwrap();
+        keyboard.press("ctrl++".parse().unwrap()).unwrap();
+        keyboard
+            .press(Chord::new(Modifiers::CTRL, Key::Char('😀')))
+            .unwrap();
+
+        // The spare keycode is unmapped again after the release
+        assert!(keyboard.remapped().is_empty());
+        let display = keyboard.display();
+        assert!(display.find_key(char_to_keysym('😀')).is_none());
+    }
+
+    #[test]
+    fn test_held_keys_released_on_panic() {
+        if std::env::var_os("DISPLAY").is_none() {
+            return;
+        }
+        let keyboard = Keyboard::new().unwrap();
+        keyboard.press("ctrl+shift+f5".parse().unwrap()).unwrap();
+
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let held = keyboard.hold(Modifiers::CTRL | Modifiers::ALT).unwrap();
+            assert_eq!(held.keys(), &[Key::Ctrl, Key::Alt]);
+            panic!("script failed while holding modifiers");
+        }));
+        assert!(panicked.is_err());
+
+        // Released keys can be pressed and released again cleanly
+        let held = keyboard.hold(Modifiers::CTRL).unwrap();
+        held.release().unwrap();
     }
 }
@@ -1,12 +1,18 @@
 //! SikuliX Platform - OS-specific screen capture and input automation
 
+pub mod backend;
+pub mod capture_stream;
+pub mod key;
 pub mod keyboard;
 pub mod mouse;
 pub mod screen;
 
-pub use keyboard::Keyboard;
-pub use mouse::Mouse;
-pub use screen::Screen;
+pub use backend::SystemInput;
+pub use capture_stream::{CaptureStats, CaptureStream, Frame};
+pub use key::{Chord, Key, Modifiers};
+pub use keyboard::{HeldKeys, Keyboard};
+pub use mouse::{Easing, Mouse, MouseAnimator, MouseButton, PathStep, WheelDirection};
+pub use screen::{Monitor, Screen};
 
 // Platform-specific implementations
 #[cfg(windows)]
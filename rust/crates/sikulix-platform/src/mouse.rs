@@ -1,22 +1,425 @@
 //! Mouse control
 
+pub use sikulix_core::MouseButton;
 use sikulix_core::{Location, Result};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[cfg(target_os = "linux")]
+use crate::platform::linux::XDisplay;
+#[cfg(not(target_os = "linux"))]
+use sikulix_core::Error;
+#[cfg(target_os = "linux")]
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Pause after pressing the button of a drag and before releasing it, so
+/// applications notice the drag (Java's `DelayBeforeDrag`/`DelayBeforeDrop`)
+const DRAG_DELAY: Duration = Duration::from_millis(300);
+
+/// Time between two pointer positions of an animated move
+const MOVE_STEP: Duration = Duration::from_millis(10);
+
+/// Progress curve of an animated pointer move
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Easing {
+    /// Jump straight to the target
+    Instant,
+    /// Constant speed
+    Linear,
+    /// Fast start that slows down towards the target, like Java's
+    /// `AnimatorOutQuarticEase`
+    #[default]
+    QuarticEaseOut,
+}
+
+impl Easing {
+    /// Map the elapsed fraction `t` of a move (0..=1) to the fraction of
+    /// the distance covered
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Instant => 1.0,
+            Easing::Linear => t,
+            Easing::QuarticEaseOut => 1.0 - (1.0 - t).powi(4),
+        }
+    }
+}
+
+/// One position of an animated pointer move
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathStep {
+    /// Time since the start of the move
+    pub at: Duration,
+
+    /// Pointer location
+    pub location: Location,
+}
+
+/// How `Mouse::move_to` travels to its target
+///
+/// The default follows a quartic ease-out curve for half a second, like
+/// the Java version's default `MoveMouseDelay`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MouseAnimator {
+    easing: Easing,
+    duration: Duration,
+}
+
+impl MouseAnimator {
+    /// Create an animator following `easing` for `duration`
+    pub fn new(easing: Easing, duration: Duration) -> Self {
+        Self { easing, duration }
+    }
+
+    /// Create an animator that jumps straight to the target
+    pub fn instant() -> Self {
+        Self::new(Easing::Instant, Duration::ZERO)
+    }
+
+    /// Get the progress curve
+    pub fn easing(&self) -> Easing {
+        self.easing
+    }
+
+    /// Get the duration of a move
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Compute the pointer positions of a move from `from` to `to`
+    ///
+    /// Positions are `MOVE_STEP` apart in time and the last one is always
+    /// `to`, reached after the animator's duration.
+    pub fn path(&self, from: Location, to: Location) -> Vec<PathStep> {
+        if self.easing == Easing::Instant || self.duration.is_zero() || from == to {
+            return vec![PathStep {
+                at: Duration::ZERO,
+                location: to,
+            }];
+        }
+
+        let steps = self
+            .duration
+            .as_nanos()
+            .div_ceil(MOVE_STEP.as_nanos())
+            .max(1) as u32;
+        let (dx, dy) = ((to.x - from.x) as f64, (to.y - from.y) as f64);
+        (1..=steps)
+            .map(|i| {
+                let progress = self.easing.apply(i as f64 / steps as f64);
+                PathStep {
+                    at: self.duration * i / steps,
+                    location: Location::new(
+                        from.x + (dx * progress).round() as i32,
+                        from.y + (dy * progress).round() as i32,
+                    ),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Default for MouseAnimator {
+    fn default() -> Self {
+        Self::new(Easing::QuarticEaseOut, Duration::from_millis(500))
+    }
+}
+
+/// X11 button number of `button`
+#[cfg(target_os = "linux")]
+fn x_button(button: MouseButton) -> u32 {
+    match button {
+        MouseButton::Left => 1,
+        MouseButton::Middle => 2,
+        MouseButton::Right => 3,
+    }
+}
+
+/// Direction of a mouse wheel step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WheelDirection {
+    /// Scroll the content up (wheel away from the user)
+    Up,
+    /// Scroll the content down (wheel towards the user)
+    Down,
+    /// Scroll the content left
+    Left,
+    /// Scroll the content right
+    Right,
+}
+
+#[cfg(target_os = "linux")]
+impl WheelDirection {
+    /// X11 button number, wheels are reported as buttons 4 to 7
+    fn x_button(self) -> u32 {
+        match self {
+            WheelDirection::Up => 4,
+            WheelDirection::Down => 5,
+            WheelDirection::Left => 6,
+            WheelDirection::Right => 7,
+        }
+    }
+}
 
 /// Mouse control interface
+///
+/// On Linux the pointer is driven through the XTest extension of the X
+/// display named by `DISPLAY`. Locations are in virtual-desktop
+/// coordinates, the same as `Screen::bounds`.
 pub struct Mouse {
-    // Will be implemented in Phase 2
+    /// Connection used to fake input, one caller at a time
+    #[cfg(target_os = "linux")]
+    display: Mutex<XDisplay>,
+
+    /// Path followed by `move_to`
+    animator: MouseAnimator,
 }
 
 impl Mouse {
+    /// Connect to the display
+    ///
+    /// # Errors
+    /// Returns `Error::Platform` if the display cannot be opened or does
+    /// not support faking input.
+    #[cfg(target_os = "linux")]
     pub fn new() -> Result<Self> {
-        Ok(Self {})
+        let display = XDisplay::open()?;
+        display.require_xtest()?;
+        Ok(Self {
+            display: Mutex::new(display),
+            animator: MouseAnimator::default(),
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            animator: MouseAnimator::default(),
+        })
+    }
+
+    /// Set how `move_to` travels to its target
+    pub fn with_animator(mut self, animator: MouseAnimator) -> Self {
+        self.animator = animator;
+        self
+    }
+
+    /// Get how `move_to` travels to its target
+    pub fn animator(&self) -> MouseAnimator {
+        self.animator
+    }
+
+    /// Get the current pointer location
+    #[cfg(target_os = "linux")]
+    pub fn location(&self) -> Result<Location> {
+        let (x, y) = self.display().pointer_position()?;
+        Ok(Location::new(x, y))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn location(&self) -> Result<Location> {
+        Err(unsupported())
+    }
+
+    /// Move the pointer to `location` along the animator's path
+    pub fn move_to(&self, location: Location) -> Result<()> {
+        let from = match self.animator.easing {
+            Easing::Instant => location,
+            _ => self.location()?,
+        };
+
+        let start = Instant::now();
+        for step in self.animator.path(from, location) {
+            let wait = step.at.saturating_sub(start.elapsed());
+            if !wait.is_zero() {
+                thread::sleep(wait);
+            }
+            self.warp(step.location)?;
+        }
+        Ok(())
+    }
+
+    /// Press `button` without releasing it
+    #[cfg(target_os = "linux")]
+    pub fn button_down(&self, button: MouseButton) -> Result<()> {
+        self.display().fake_button(x_button(button), true)
     }
 
-    pub fn move_to(&self, _location: Location) -> Result<()> {
-        unimplemented!("Mouse control - Phase 2")
+    #[cfg(not(target_os = "linux"))]
+    pub fn button_down(&self, _button: MouseButton) -> Result<()> {
+        Err(unsupported())
     }
 
+    /// Release `button`
+    #[cfg(target_os = "linux")]
+    pub fn button_up(&self, button: MouseButton) -> Result<()> {
+        self.display().fake_button(x_button(button), false)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn button_up(&self, _button: MouseButton) -> Result<()> {
+        Err(unsupported())
+    }
+
+    /// Click the left button at the current location
     pub fn click(&self) -> Result<()> {
-        unimplemented!("Mouse control - Phase 2")
+        self.click_button(MouseButton::Left)
+    }
+
+    /// Click the right button at the current location
+    pub fn right_click(&self) -> Result<()> {
+        self.click_button(MouseButton::Right)
+    }
+
+    /// Click the middle button at the current location
+    pub fn middle_click(&self) -> Result<()> {
+        self.click_button(MouseButton::Middle)
+    }
+
+    /// Press and release `button` at the current location
+    pub fn click_button(&self, button: MouseButton) -> Result<()> {
+        self.button_down(button)?;
+        self.button_up(button)
+    }
+
+    /// Click the left button twice at the current location
+    pub fn double_click(&self) -> Result<()> {
+        self.click_button(MouseButton::Left)?;
+        self.click_button(MouseButton::Left)
+    }
+
+    /// Drag with the left button from `from` and drop at `to`
+    ///
+    /// The button is released even if moving to `to` fails.
+    pub fn drag_drop(&self, from: Location, to: Location) -> Result<()> {
+        self.move_to(from)?;
+        self.button_down(MouseButton::Left)?;
+        thread::sleep(DRAG_DELAY);
+
+        let moved = self.move_to(to);
+        if moved.is_ok() {
+            thread::sleep(DRAG_DELAY);
+        }
+        let released = self.button_up(MouseButton::Left);
+        moved.and(released)
+    }
+
+    /// Put the pointer at `location` at once
+    #[cfg(target_os = "linux")]
+    fn warp(&self, location: Location) -> Result<()> {
+        self.display().fake_motion(location.x, location.y)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn warp(&self, _location: Location) -> Result<()> {
+        Err(unsupported())
+    }
+
+    /// Turn the wheel by `steps` notches in `direction`
+    #[cfg(target_os = "linux")]
+    pub fn wheel(&self, direction: WheelDirection, steps: u32) -> Result<()> {
+        let display = self.display();
+        for _ in 0..steps {
+            display.fake_button(direction.x_button(), true)?;
+            display.fake_button(direction.x_button(), false)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn wheel(&self, _direction: WheelDirection, _steps: u32) -> Result<()> {
+        Err(unsupported())
+    }
+
+    #[cfg(target_os = "linux")]
+    fn display(&self) -> MutexGuard<'_, XDisplay> {
+        // A panic while faking input leaves the connection itself usable
+        self.display.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Error of the mouse actions on platforms without an input backend yet
+#[cfg(not(target_os = "linux"))]
+fn unsupported() -> Error {
+    Error::Platform("Mouse control is not supported on this platform".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_x_button_numbers() {
+        assert_eq!(x_button(MouseButton::Left), 1);
+        assert_eq!(x_button(MouseButton::Middle), 2);
+        assert_eq!(x_button(MouseButton::Right), 3);
+        assert_eq!(WheelDirection::Up.x_button(), 4);
+        assert_eq!(WheelDirection::Right.x_button(), 7);
+    }
+
+    #[test]
+    fn test_easing_curves() {
+        assert_eq!(Easing::Linear.apply(0.25), 0.25);
+        assert_eq!(Easing::QuarticEaseOut.apply(0.5), 0.9375);
+        assert_eq!(Easing::QuarticEaseOut.apply(1.0), 1.0);
+        assert_eq!(Easing::Instant.apply(0.0), 1.0);
+    }
+
+    #[test]
+    fn test_linear_path() {
+        let animator = MouseAnimator::new(Easing::Linear, Duration::from_millis(40));
+        let path = animator.path(Location::new(0, 0), Location::new(100, -40));
+
+        let points: Vec<_> = path.iter().map(|s| (s.location.x, s.location.y)).collect();
+        assert_eq!(points, vec![(25, -10), (50, -20), (75, -30), (100, -40)]);
+        assert_eq!(path[0].at, Duration::from_millis(10));
+        assert_eq!(path[3].at, Duration::from_millis(40));
+    }
+
+    #[test]
+    fn test_eased_path_slows_down() {
+        let path = MouseAnimator::default().path(Location::new(0, 0), Location::new(1000, 0));
+        assert_eq!(path.len(), 50);
+        assert_eq!(path.last().unwrap().location, Location::new(1000, 0));
+
+        // Steps shrink towards the target (up to rounding) and never overshoot it
+        let xs: Vec<_> = path.iter().map(|s| s.location.x).collect();
+        assert!(xs.windows(3).all(|w| w[1] - w[0] + 1 >= w[2] - w[1]));
+        assert!(xs.iter().all(|&x| x <= 1000));
+    }
+
+    #[test]
+    fn test_instant_path() {
+        let to = Location::new(5, 6);
+        let path = MouseAnimator::instant().path(Location::new(0, 0), to);
+        assert_eq!(
+            path,
+            vec![PathStep {
+                at: Duration::ZERO,
+                location: to
+            }]
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_move_and_click() {
+        if std::env::var_os("DISPLAY").is_none() {
+            return;
+        }
+        let mouse = Mouse::new().unwrap().with_animator(MouseAnimator::new(
+            Easing::Linear,
+            Duration::from_millis(50),
+        ));
+        let target = Location::new(20, 30);
+
+        mouse.move_to(target).unwrap();
+        assert_eq!(mouse.location().unwrap(), target);
+
+        mouse.click().unwrap();
+        mouse.wheel(WheelDirection::Down, 2).unwrap();
+        mouse.drag_drop(target, Location::new(40, 50)).unwrap();
+        assert_eq!(mouse.location().unwrap(), Location::new(40, 50));
     }
 }
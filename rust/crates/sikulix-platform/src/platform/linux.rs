@@ -1,4 +1,1114 @@
-//! Linux-specific platform implementation
+//! Linux-specific platform implementation (X11)
 
-// Placeholder for Linux-specific functionality
-// Will be implemented in Phase 2
+use crate::key::Key;
+use crate::screen::Monitor;
+use sikulix_core::{Error, Region, Result, ScreenImage};
+use std::ffi::{c_void, CStr};
+use std::os::raw::{c_char, c_int, c_uint, c_ulong};
+use std::ptr;
+use std::slice;
+use std::sync::{Mutex, MutexGuard, Once, PoisonError};
+use tracing::{debug, trace};
+use x11::{keysym, xinerama, xlib, xrandr, xshm, xtest};
+
+/// Connection to an X server
+///
+/// Closed when dropped. Xlib connections are not thread-safe, so users must
+/// make sure only one thread at a time calls into a connection (`Screen`
+/// keeps it behind a mutex).
+pub struct XDisplay {
+    raw: *mut xlib::Display,
+}
+
+// The connection is only ever used by one thread at a time (see above), and
+// XInitThreads is called before the first connection is opened.
+unsafe impl Send for XDisplay {}
+
+impl XDisplay {
+    /// Connect to the X server named by the `DISPLAY` environment variable
+    pub fn open() -> Result<Self> {
+        static INIT_THREADS: Once = Once::new();
+        INIT_THREADS.call_once(|| unsafe {
+            xlib::XInitThreads();
+        });
+
+        let raw = unsafe { xlib::XOpenDisplay(ptr::null()) };
+        if raw.is_null() {
+            let name = std::env::var("DISPLAY").unwrap_or_default();
+            return Err(Error::Platform(format!(
+                "Cannot open X display {:?} (is DISPLAY set and the server running?)",
+                name
+            )));
+        }
+        debug!("Opened X display");
+        Ok(Self { raw })
+    }
+
+    /// Get the raw Xlib display pointer
+    pub fn as_ptr(&self) -> *mut xlib::Display {
+        self.raw
+    }
+
+    /// Get the root window of the default screen
+    pub fn root(&self) -> xlib::Window {
+        unsafe { xlib::XDefaultRootWindow(self.raw) }
+    }
+
+    /// Get the bounds of the whole X screen (the virtual desktop)
+    pub fn root_bounds(&self) -> Result<Region> {
+        let mut root = 0;
+        let (mut x, mut y) = (0, 0);
+        let (mut w, mut h, mut border, mut depth) = (0, 0, 0, 0);
+        let ok = unsafe {
+            xlib::XGetGeometry(
+                self.raw,
+                self.root(),
+                &mut root,
+                &mut x,
+                &mut y,
+                &mut w,
+                &mut h,
+                &mut border,
+                &mut depth,
+            )
+        };
+        if ok == 0 {
+            return Err(Error::Platform(
+                "XGetGeometry failed for the root window".to_string(),
+            ));
+        }
+        Ok(Region::new(0, 0, w as i32, h as i32))
+    }
+
+    /// Capture `region` of the root window as BGR pixels
+    ///
+    /// The region is clipped to the screen; the returned image covers the
+    /// clipped area, see `ScreenImage::region`.
+    pub fn capture(&self, region: Region) -> Result<ScreenImage> {
+        let area = self.root_bounds()?.intersection(&region).ok_or_else(|| {
+            Error::InvalidRegion(format!(
+                "Capture region {:?} lies outside the screen",
+                region
+            ))
+        })?;
+
+        let image = unsafe {
+            xlib::XGetImage(
+                self.raw,
+                self.root(),
+                area.x,
+                area.y,
+                area.w as u32,
+                area.h as u32,
+                xlib::XAllPlanes(),
+                xlib::ZPixmap,
+            )
+        };
+        if image.is_null() {
+            return Err(Error::Platform(format!("XGetImage failed for {:?}", area)));
+        }
+
+        let result = unsafe { ximage_to_bgr(&*image, area) };
+        unsafe {
+            xlib::XDestroyImage(image);
+        }
+        trace!("Captured {:?}", area);
+        result
+    }
+}
+
+impl XDisplay {
+    /// Enumerate the monitors (heads) of the virtual desktop
+    ///
+    /// Asks RandR (1.5 monitors) and Xinerama, and uses whichever reports
+    /// more heads; Xvfb, for one, exposes its screens through Xinerama
+    /// only. Without either extension the whole desktop is one monitor.
+    /// The result is in server order; see `Screen::monitors` for indexing.
+    pub fn monitors(&self) -> Result<Vec<Monitor>> {
+        let randr = self.randr_monitors();
+        let xinerama = self.xinerama_monitors();
+        debug!(
+            "RandR reports {} monitors, Xinerama {}",
+            randr.len(),
+            xinerama.len()
+        );
+
+        let heads = if xinerama.len() > randr.len() {
+            xinerama
+        } else {
+            randr
+        };
+        if !heads.is_empty() {
+            return Ok(heads);
+        }
+        Ok(vec![Monitor {
+            index: 0,
+            name: None,
+            bounds: self.root_bounds()?,
+            primary: true,
+        }])
+    }
+
+    /// Monitors reported by RandR 1.5, empty if unsupported
+    fn randr_monitors(&self) -> Vec<Monitor> {
+        let (mut event_base, mut error_base) = (0, 0);
+        let (mut major, mut minor) = (0, 0);
+        let supported = unsafe {
+            xrandr::XRRQueryExtension(self.raw, &mut event_base, &mut error_base) != xlib::False
+                && xrandr::XRRQueryVersion(self.raw, &mut major, &mut minor) != 0
+        };
+        if !supported || (major, minor) < (1, 5) {
+            return Vec::new();
+        }
+
+        let mut count = 0;
+        let infos =
+            unsafe { xrandr::XRRGetMonitors(self.raw, self.root(), xlib::True, &mut count) };
+        if infos.is_null() {
+            return Vec::new();
+        }
+
+        let monitors = unsafe { slice::from_raw_parts(infos, count.max(0) as usize) }
+            .iter()
+            .enumerate()
+            .map(|(index, info)| Monitor {
+                index,
+                name: self.atom_name(info.name),
+                bounds: Region::new(info.x, info.y, info.width, info.height),
+                primary: info.primary != xlib::False,
+            })
+            .collect();
+        unsafe { xrandr::XRRFreeMonitors(infos) };
+        monitors
+    }
+
+    /// Screens reported by an active Xinerama, empty otherwise
+    fn xinerama_monitors(&self) -> Vec<Monitor> {
+        let (mut event_base, mut error_base) = (0, 0);
+        let active = unsafe {
+            xinerama::XineramaQueryExtension(self.raw, &mut event_base, &mut error_base)
+                != xlib::False
+                && xinerama::XineramaIsActive(self.raw) != xlib::False
+        };
+        if !active {
+            return Vec::new();
+        }
+
+        let mut count = 0;
+        let infos = unsafe { xinerama::XineramaQueryScreens(self.raw, &mut count) };
+        if infos.is_null() {
+            return Vec::new();
+        }
+
+        let monitors = unsafe { slice::from_raw_parts(infos, count.max(0) as usize) }
+            .iter()
+            .enumerate()
+            .map(|(index, info)| Monitor {
+                index,
+                name: None,
+                bounds: Region::new(
+                    info.x_org as i32,
+                    info.y_org as i32,
+                    info.width as i32,
+                    info.height as i32,
+                ),
+                primary: info.screen_number == 0,
+            })
+            .collect();
+        unsafe { xlib::XFree(infos as *mut c_void) };
+        monitors
+    }
+
+    /// Look up the name of an atom
+    fn atom_name(&self, atom: xlib::Atom) -> Option<String> {
+        if atom == 0 {
+            return None;
+        }
+        unsafe {
+            let raw = xlib::XGetAtomName(self.raw, atom);
+            if raw.is_null() {
+                return None;
+            }
+            let name = CStr::from_ptr(raw).to_string_lossy().into_owned();
+            xlib::XFree(raw as *mut c_void);
+            Some(name)
+        }
+    }
+}
+
+impl XDisplay {
+    /// Check that the server supports XTest, which fakes input events
+    pub fn require_xtest(&self) -> Result<()> {
+        let (mut event_base, mut error_base) = (0, 0);
+        let (mut major, mut minor) = (0, 0);
+        let supported = unsafe {
+            xtest::XTestQueryExtension(
+                self.raw,
+                &mut event_base,
+                &mut error_base,
+                &mut major,
+                &mut minor,
+            )
+        };
+        if supported == 0 {
+            return Err(Error::Platform(
+                "The X server does not support the XTest extension".to_string(),
+            ));
+        }
+        debug!("XTest {}.{}", major, minor);
+        Ok(())
+    }
+
+    /// Get the pointer position in root window coordinates
+    pub fn pointer_position(&self) -> Result<(i32, i32)> {
+        let (mut root, mut child) = (0, 0);
+        let (mut x, mut y, mut win_x, mut win_y) = (0, 0, 0, 0);
+        let mut mask = 0;
+        let ok = unsafe {
+            xlib::XQueryPointer(
+                self.raw,
+                self.root(),
+                &mut root,
+                &mut child,
+                &mut x,
+                &mut y,
+                &mut win_x,
+                &mut win_y,
+                &mut mask,
+            )
+        };
+        if ok == xlib::False {
+            return Err(Error::Platform(
+                "The pointer is on another X screen".to_string(),
+            ));
+        }
+        Ok((x, y))
+    }
+
+    /// Move the pointer to (`x`, `y`) in root window coordinates
+    pub fn fake_motion(&self, x: i32, y: i32) -> Result<()> {
+        // Screen -1 is the screen the pointer is on
+        let ok = unsafe { xtest::XTestFakeMotionEvent(self.raw, -1, x, y, xlib::CurrentTime) };
+        self.flush_fake(ok, || format!("XTest motion to ({}, {})", x, y))
+    }
+
+    /// Press or release pointer button `button` (1 is the left button)
+    pub fn fake_button(&self, button: c_uint, press: bool) -> Result<()> {
+        let is_press = if press { xlib::True } else { xlib::False };
+        let ok =
+            unsafe { xtest::XTestFakeButtonEvent(self.raw, button, is_press, xlib::CurrentTime) };
+        self.flush_fake(ok, || {
+            let action = if press { "press" } else { "release" };
+            format!("XTest {} of button {}", action, button)
+        })
+    }
+
+    /// Run `requests` and report X errors they cause as `Error::Platform`
+    ///
+    /// Xlib's default handler ends the process on any X error. The error
+    /// handler is process-wide, so traps of all connections run one at a
+    /// time, and errors of other connections still reach the previous
+    /// handler.
+    pub fn trap_errors<T>(&self, what: &str, requests: impl FnOnce() -> T) -> Result<T> {
+        let _serialised = ERROR_TRAP.lock().unwrap_or_else(PoisonError::into_inner);
+        let first_serial = unsafe {
+            // Errors of earlier requests still go to the previous handler
+            xlib::XSync(self.raw, xlib::False);
+            xlib::XNextRequest(self.raw)
+        };
+        *trap() = Some(Trap {
+            display: self.raw as usize,
+            first_serial,
+            error_code: None,
+            previous: None,
+        });
+        let previous = unsafe { xlib::XSetErrorHandler(Some(trap_error)) };
+        if let Some(trap) = trap().as_mut() {
+            trap.previous = previous;
+        }
+
+        let value = requests();
+        unsafe {
+            xlib::XSync(self.raw, xlib::False);
+            xlib::XSetErrorHandler(previous);
+        }
+        match trap().take().and_then(|trap| trap.error_code) {
+            Some(code) => Err(Error::Platform(format!(
+                "{} failed with X error code {}",
+                what, code
+            ))),
+            None => Ok(value),
+        }
+    }
+
+    /// Send a faked event to the server, or report it as failed
+    fn flush_fake(&self, status: c_int, event: impl FnOnce() -> String) -> Result<()> {
+        if status == 0 {
+            return Err(Error::Platform(format!("{} failed", event())));
+        }
+        unsafe {
+            xlib::XSync(self.raw, xlib::False);
+        }
+        Ok(())
+    }
+}
+
+/// A key press on the current keyboard mapping
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyStroke {
+    /// Key to press
+    pub keycode: xlib::KeyCode,
+
+    /// Modifier keys held while pressing it, outermost first
+    pub modifiers: Vec<xlib::KeyCode>,
+}
+
+impl XDisplay {
+    /// Find the key that produces `keysym` in the active XKB layout group
+    ///
+    /// Keys on the plain level are preferred over shifted ones. Levels
+    /// that need a modifier missing from the mapping (e.g. no AltGr key)
+    /// are not considered; `None` means the keysym cannot be typed with
+    /// the current mapping.
+    pub fn find_key(&self, keysym: xlib::KeySym) -> Option<KeyStroke> {
+        let group = self.keyboard_group();
+        let shift = self.modifier_keycode(keysym::XK_Shift_L);
+        let level3 = self.modifier_keycode(keysym::XK_ISO_Level3_Shift);
+        let (min, max) = self.keycode_range();
+
+        for level in 0..4 {
+            let Some(modifiers) = level_modifiers(level, shift, level3) else {
+                continue;
+            };
+            for keycode in min..=max {
+                let found = unsafe { xlib::XkbKeycodeToKeysym(self.raw, keycode, group, level) };
+                if found == keysym {
+                    return Some(KeyStroke { keycode, modifiers });
+                }
+            }
+        }
+        None
+    }
+
+    /// Find a keycode without any keysyms, which can be remapped to type
+    /// characters missing from the layout
+    pub fn spare_keycode(&self) -> Option<xlib::KeyCode> {
+        let (min, max) = self.keycode_range();
+        let count = (max - min) as c_int + 1;
+        let mut per_keycode = 0;
+        let mapping = unsafe { xlib::XGetKeyboardMapping(self.raw, min, count, &mut per_keycode) };
+        if mapping.is_null() {
+            return None;
+        }
+
+        let per_keycode = per_keycode.max(0) as usize;
+        let syms = unsafe { slice::from_raw_parts(mapping, count as usize * per_keycode) };
+        // Search from the top, where keyboards rarely have physical keys
+        let spare = (min..=max).rev().find(|&keycode| {
+            let start = (keycode - min) as usize * per_keycode;
+            syms[start..start + per_keycode].iter().all(|&sym| sym == 0)
+        });
+        unsafe { xlib::XFree(mapping as *mut c_void) };
+        spare
+    }
+
+    /// Map `keycode` to `keysym` alone, or unmap it with `NoSymbol` (0)
+    ///
+    /// # Errors
+    /// Returns `Error::Platform` if the server rejects the new mapping.
+    pub fn remap_keycode(&self, keycode: xlib::KeyCode, keysym: xlib::KeySym) -> Result<()> {
+        let mut syms = [keysym];
+        self.trap_errors("XChangeKeyboardMapping", || unsafe {
+            xlib::XChangeKeyboardMapping(self.raw, keycode as c_int, 1, syms.as_mut_ptr(), 1);
+        })?;
+        trace!("Mapped keycode {} to keysym {:#x}", keycode, keysym);
+        Ok(())
+    }
+
+    /// Press or release the key `keycode`
+    pub fn fake_key(&self, keycode: xlib::KeyCode, press: bool) -> Result<()> {
+        let is_press = if press { xlib::True } else { xlib::False };
+        let ok = unsafe {
+            xtest::XTestFakeKeyEvent(self.raw, keycode as c_uint, is_press, xlib::CurrentTime)
+        };
+        self.flush_fake(ok, || {
+            let action = if press { "press" } else { "release" };
+            format!("XTest {} of keycode {}", action, keycode)
+        })
+    }
+
+    /// Press and release the key of `stroke` while holding its modifiers
+    ///
+    /// Every key that was pressed is released again, even after an error.
+    pub fn fake_stroke(&self, stroke: &KeyStroke) -> Result<()> {
+        let mut pressed = Vec::with_capacity(stroke.modifiers.len() + 1);
+        let mut result = Ok(());
+        for &keycode in stroke.modifiers.iter().chain([&stroke.keycode]) {
+            result = self.fake_key(keycode, true);
+            if result.is_err() {
+                break;
+            }
+            pressed.push(keycode);
+        }
+        for &keycode in pressed.iter().rev() {
+            let released = self.fake_key(keycode, false);
+            result = result.and(released);
+        }
+        result
+    }
+
+    /// Get the active layout group of the core keyboard
+    fn keyboard_group(&self) -> c_int {
+        let mut state: xlib::XkbStateRec = unsafe { std::mem::zeroed() };
+        unsafe {
+            xlib::XkbGetState(self.raw, XKB_USE_CORE_KBD, &mut state);
+        }
+        state.group as c_int
+    }
+
+    /// Get the keycode of a modifier key such as Shift, if it is mapped
+    fn modifier_keycode(&self, keysym: c_uint) -> Option<xlib::KeyCode> {
+        let keycode = unsafe { xlib::XKeysymToKeycode(self.raw, keysym as xlib::KeySym) };
+        (keycode != 0).then_some(keycode)
+    }
+
+    /// Get the lowest and highest keycode of the keyboard
+    fn keycode_range(&self) -> (xlib::KeyCode, xlib::KeyCode) {
+        let (mut min, mut max) = (0, 0);
+        unsafe {
+            xlib::XDisplayKeycodes(self.raw, &mut min, &mut max);
+        }
+        (
+            min.clamp(8, 255) as xlib::KeyCode,
+            max.clamp(8, 255) as xlib::KeyCode,
+        )
+    }
+}
+
+impl Drop for XDisplay {
+    fn drop(&mut self) {
+        unsafe {
+            xlib::XCloseDisplay(self.raw);
+        }
+    }
+}
+
+/// Repeated captures of one screen area through a MIT-SHM shared memory segment
+///
+/// The X server writes each capture straight into the segment, so grabbing
+/// a frame neither allocates nor copies pixels through the X socket. Uses
+/// its own connection; the segment is released when dropped.
+pub struct ShmCapture {
+    /// Connection the segment is attached to
+    display: XDisplay,
+
+    /// XImage whose data lives in the shared segment
+    image: *mut xlib::XImage,
+
+    /// The shared memory segment; boxed because the XImage points to it
+    segment: Box<xshm::XShmSegmentInfo>,
+
+    /// Captured screen area
+    area: Region,
+}
+
+// Owns its connection and segment exclusively
+unsafe impl Send for ShmCapture {}
+
+impl ShmCapture {
+    /// Set up shared memory captures of `region`, clipped to the screen
+    ///
+    /// # Errors
+    /// Returns `Error::Platform` if the X server does not support MIT-SHM
+    /// (e.g. a remote display), and `Error::InvalidRegion` if the region
+    /// lies outside the screen.
+    pub fn new(region: Region) -> Result<Self> {
+        let display = XDisplay::open()?;
+        let area = display
+            .root_bounds()?
+            .intersection(&region)
+            .ok_or_else(|| {
+                Error::InvalidRegion(format!(
+                    "Capture region {:?} lies outside the screen",
+                    region
+                ))
+            })?;
+
+        let raw = display.as_ptr();
+        if unsafe { xshm::XShmQueryExtension(raw) } == xlib::False {
+            return Err(Error::Platform(
+                "X server does not support the MIT-SHM extension".to_string(),
+            ));
+        }
+
+        let mut segment = Box::new(xshm::XShmSegmentInfo {
+            shmseg: 0,
+            shmid: -1,
+            shmaddr: ptr::null_mut(),
+            readOnly: xlib::False,
+        });
+        let image = unsafe {
+            let screen = xlib::XDefaultScreen(raw);
+            xshm::XShmCreateImage(
+                raw,
+                xlib::XDefaultVisual(raw, screen),
+                xlib::XDefaultDepth(raw, screen) as u32,
+                xlib::ZPixmap,
+                ptr::null_mut(),
+                &mut *segment,
+                area.w as u32,
+                area.h as u32,
+            )
+        };
+        if image.is_null() {
+            return Err(Error::Platform("XShmCreateImage failed".to_string()));
+        }
+
+        // From here on Drop releases whatever has been set up
+        let mut capture = Self {
+            display,
+            image,
+            segment,
+            area,
+        };
+        capture.attach_segment()?;
+        debug!("Attached shared memory capture of {:?}", area);
+        Ok(capture)
+    }
+
+    /// Create, map and attach the shared memory segment backing the image
+    fn attach_segment(&mut self) -> Result<()> {
+        let image = unsafe { &mut *self.image };
+        let size = image.bytes_per_line as usize * image.height as usize;
+
+        let shmid = unsafe { libc::shmget(libc::IPC_PRIVATE, size, libc::IPC_CREAT | 0o600) };
+        if shmid < 0 {
+            return Err(Error::Platform(format!(
+                "shmget failed: {}",
+                std::io::Error::last_os_error()
+            )));
+        }
+        self.segment.shmid = shmid;
+
+        let addr = unsafe { libc::shmat(shmid, ptr::null(), 0) };
+        if addr as isize == -1 {
+            return Err(Error::Platform(format!(
+                "shmat failed: {}",
+                std::io::Error::last_os_error()
+            )));
+        }
+        self.segment.shmaddr = addr as *mut c_char;
+        image.data = addr as *mut c_char;
+
+        // A server that cannot reach our memory (e.g. over the network)
+        // answers with an X error, which would otherwise end the process
+        let raw = self.display.as_ptr();
+        let segment = &mut *self.segment;
+        let attached = self
+            .display
+            .trap_errors("XShmAttach", || unsafe { xshm::XShmAttach(raw, segment) });
+        // Removed once both sides detach, even if the process dies
+        unsafe {
+            libc::shmctl(shmid, libc::IPC_RMID, ptr::null_mut());
+        }
+        match attached {
+            Ok(status) if status != xlib::False => Ok(()),
+            Ok(_) => {
+                self.segment.shmseg = 0;
+                Err(Error::Platform("XShmAttach failed".to_string()))
+            }
+            Err(e) => {
+                self.segment.shmseg = 0;
+                Err(e)
+            }
+        }
+    }
+
+    /// Get the captured screen area
+    pub fn area(&self) -> Region {
+        self.area
+    }
+
+    /// Grab the current screen contents into the shared segment
+    pub fn grab(&mut self) -> Result<()> {
+        let ok = unsafe {
+            xshm::XShmGetImage(
+                self.display.as_ptr(),
+                self.display.root(),
+                self.image,
+                self.area.x,
+                self.area.y,
+                // The binding declares the plane mask as c_uint; all 32 planes
+                xlib::XAllPlanes() as c_uint,
+            )
+        };
+        if ok == xlib::False {
+            return Err(Error::Platform(format!(
+                "XShmGetImage failed for {:?}",
+                self.area
+            )));
+        }
+        Ok(())
+    }
+
+    /// Convert the last grabbed frame into `out`, which must cover `area`
+    pub fn copy_into(&self, out: &mut ScreenImage) -> Result<()> {
+        if out.region() != self.area {
+            return Err(Error::InvalidParameter(format!(
+                "Frame buffer covers {:?}, capture covers {:?}",
+                out.region(),
+                self.area
+            )));
+        }
+        unsafe { ximage_into_bgr(&*self.image, out) }
+    }
+}
+
+impl Drop for ShmCapture {
+    fn drop(&mut self) {
+        let raw = self.display.as_ptr();
+        unsafe {
+            if self.segment.shmseg != 0 {
+                xshm::XShmDetach(raw, &mut *self.segment);
+                xlib::XSync(raw, xlib::False);
+            }
+            // The pixel data belongs to the segment, not to Xlib's allocator
+            (*self.image).data = ptr::null_mut();
+            xlib::XDestroyImage(self.image);
+            if !self.segment.shmaddr.is_null() {
+                libc::shmdt(self.segment.shmaddr as *const libc::c_void);
+            } else if self.segment.shmid >= 0 {
+                libc::shmctl(self.segment.shmid, libc::IPC_RMID, ptr::null_mut());
+            }
+        }
+        debug!("Released shared memory capture of {:?}", self.area);
+    }
+}
+
+/// Held while a connection traps X errors, since the handler is process-wide
+static ERROR_TRAP: Mutex<()> = Mutex::new(());
+
+/// The connection currently trapping X errors, read by `trap_error`
+static TRAP: Mutex<Option<Trap>> = Mutex::new(None);
+
+/// Errors being trapped by `XDisplay::trap_errors`
+struct Trap {
+    /// Address of the trapping connection
+    display: usize,
+
+    /// Serial of the first request whose errors are trapped
+    first_serial: c_ulong,
+
+    /// Code of the first trapped error
+    error_code: Option<u8>,
+
+    /// Handler that receives the errors of other connections
+    previous: Option<ErrorHandler>,
+}
+
+type ErrorHandler = unsafe extern "C" fn(*mut xlib::Display, *mut xlib::XErrorEvent) -> c_int;
+
+fn trap() -> MutexGuard<'static, Option<Trap>> {
+    TRAP.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// X error handler installed by `XDisplay::trap_errors`
+///
+/// Records errors of the trapping connection's requests and hands all
+/// others to the handler that was installed before.
+unsafe extern "C" fn trap_error(
+    display: *mut xlib::Display,
+    event: *mut xlib::XErrorEvent,
+) -> c_int {
+    let previous = match trap().as_mut() {
+        Some(trap) if trap.display == display as usize && (*event).serial >= trap.first_serial => {
+            trap.error_code.get_or_insert((*event).error_code);
+            return 0;
+        }
+        Some(trap) => trap.previous,
+        None => None,
+    };
+    match previous {
+        Some(handler) => handler(display, event),
+        None => 0,
+    }
+}
+
+/// Copy the pixels of an XImage covering `area` into a BGR image
+///
+/// # Safety
+/// `image.data` must point to `bytes_per_line * height` readable bytes.
+pub unsafe fn ximage_to_bgr(image: &xlib::XImage, area: Region) -> Result<ScreenImage> {
+    let mut out = ScreenImage::new(area);
+    ximage_into_bgr(image, &mut out)?;
+    Ok(out)
+}
+
+/// Copy the pixels of an XImage into an existing BGR image of the same size
+///
+/// # Safety
+/// `image.data` must point to `bytes_per_line * height` readable bytes.
+pub unsafe fn ximage_into_bgr(image: &xlib::XImage, out: &mut ScreenImage) -> Result<()> {
+    let stride = image.bytes_per_line as usize;
+    let data = slice::from_raw_parts(image.data as *const u8, stride * image.height as usize);
+    PixelFormat::of(image).convert_into(data, stride, out)
+}
+
+/// Layout of the pixels of a TrueColor XImage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelFormat {
+    /// Bits used by one pixel: 16, 24 or 32
+    pub bits_per_pixel: u32,
+
+    /// Pixels are stored least significant byte first
+    pub lsb_first: bool,
+
+    /// Bits of the red channel in a pixel value
+    pub red_mask: u32,
+
+    /// Bits of the green channel in a pixel value
+    pub green_mask: u32,
+
+    /// Bits of the blue channel in a pixel value
+    pub blue_mask: u32,
+}
+
+impl PixelFormat {
+    /// Get the pixel layout of an XImage
+    pub fn of(image: &xlib::XImage) -> Self {
+        Self {
+            bits_per_pixel: image.bits_per_pixel as u32,
+            lsb_first: image.byte_order == xlib::LSBFirst,
+            red_mask: image.red_mask as u32,
+            green_mask: image.green_mask as u32,
+            blue_mask: image.blue_mask as u32,
+        }
+    }
+
+    /// Convert rows of pixels, `stride` bytes apart, into a BGR image of `area`
+    pub fn to_bgr(&self, data: &[u8], stride: usize, area: Region) -> Result<ScreenImage> {
+        let mut out = ScreenImage::new(area);
+        self.convert_into(data, stride, &mut out)?;
+        Ok(out)
+    }
+
+    /// Convert rows of pixels, `stride` bytes apart, into an existing BGR image
+    ///
+    /// `data` must hold at least as many rows and columns as `out`.
+    pub fn convert_into(&self, data: &[u8], stride: usize, out: &mut ScreenImage) -> Result<()> {
+        let bytes_per_pixel = match self.bits_per_pixel {
+            16 | 24 | 32 => (self.bits_per_pixel / 8) as usize,
+            bits => {
+                return Err(Error::Platform(format!(
+                    "Unsupported X image format: {} bits per pixel",
+                    bits
+                )))
+            }
+        };
+
+        let width = out.width() as usize;
+        for y in 0..out.height() {
+            let src = &data[y as usize * stride..][..width * bytes_per_pixel];
+            let dst = out.row_mut(y);
+
+            if self.is_bgrx() && bytes_per_pixel == 4 {
+                for (d, s) in dst.chunks_exact_mut(3).zip(src.chunks_exact(4)) {
+                    d.copy_from_slice(&s[..3]);
+                }
+                continue;
+            }
+
+            for (d, s) in dst
+                .chunks_exact_mut(3)
+                .zip(src.chunks_exact(bytes_per_pixel))
+            {
+                let pixel = self.pixel_value(s);
+                d[0] = channel(pixel, self.blue_mask);
+                d[1] = channel(pixel, self.green_mask);
+                d[2] = channel(pixel, self.red_mask);
+            }
+        }
+        Ok(())
+    }
+
+    /// Check for the common little endian `0x00RRGGBB` layout, stored as B, G, R, X
+    fn is_bgrx(&self) -> bool {
+        self.lsb_first
+            && self.red_mask == 0x00ff_0000
+            && self.green_mask == 0x0000_ff00
+            && self.blue_mask == 0x0000_00ff
+    }
+
+    /// Assemble the value of one pixel from its bytes
+    fn pixel_value(&self, bytes: &[u8]) -> u32 {
+        let fold = |acc: u32, &b: &u8| (acc << 8) | b as u32;
+        if self.lsb_first {
+            bytes.iter().rev().fold(0, fold)
+        } else {
+            bytes.iter().fold(0, fold)
+        }
+    }
+}
+
+/// Extract the channel selected by `mask` from a pixel value, scaled to 8 bits
+fn channel(pixel: u32, mask: u32) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+    let bits = mask.count_ones();
+    let value = (pixel & mask) >> mask.trailing_zeros();
+    if bits >= 8 {
+        (value >> (bits - 8)) as u8
+    } else {
+        (value * 255 / ((1 << bits) - 1)) as u8
+    }
+}
+
+/// Device id of the core keyboard for Xkb requests
+const XKB_USE_CORE_KBD: c_uint = 0x0100;
+
+/// Keysym that types `c`
+///
+/// Latin-1 characters have keysyms equal to their code point, control
+/// characters map to the keys that produce them, and everything else uses
+/// the Unicode keysym range.
+pub fn char_to_keysym(c: char) -> xlib::KeySym {
+    let keysym = match c {
+        '\n' | '\r' => keysym::XK_Return,
+        '\t' => keysym::XK_Tab,
+        '\u{8}' => keysym::XK_BackSpace,
+        '\u{1b}' => keysym::XK_Escape,
+        '\u{20}'..='\u{7e}' | '\u{a0}'..='\u{ff}' => c as c_uint,
+        _ => 0x0100_0000 | c as c_uint,
+    };
+    keysym as xlib::KeySym
+}
+
+/// Keysym of `key`
+pub fn key_to_keysym(key: Key) -> xlib::KeySym {
+    use keysym::*;
+
+    let keysym = match key {
+        Key::Char(c) => return char_to_keysym(c),
+        Key::Shift => XK_Shift_L,
+        Key::Ctrl => XK_Control_L,
+        Key::Alt => XK_Alt_L,
+        Key::Meta => XK_Super_L,
+        Key::AltGr => XK_ISO_Level3_Shift,
+        Key::Enter => XK_Return,
+        Key::Tab => XK_Tab,
+        Key::Escape => XK_Escape,
+        Key::Backspace => XK_BackSpace,
+        Key::Delete => XK_Delete,
+        Key::Insert => XK_Insert,
+        Key::Space => XK_space,
+        Key::Home => XK_Home,
+        Key::End => XK_End,
+        Key::PageUp => XK_Prior,
+        Key::PageDown => XK_Next,
+        Key::Up => XK_Up,
+        Key::Down => XK_Down,
+        Key::Left => XK_Left,
+        Key::Right => XK_Right,
+        Key::CapsLock => XK_Caps_Lock,
+        Key::NumLock => XK_Num_Lock,
+        Key::ScrollLock => XK_Scroll_Lock,
+        Key::PrintScreen => XK_Print,
+        Key::Pause => XK_Pause,
+        Key::F1 => XK_F1,
+        Key::F2 => XK_F2,
+        Key::F3 => XK_F3,
+        Key::F4 => XK_F4,
+        Key::F5 => XK_F5,
+        Key::F6 => XK_F6,
+        Key::F7 => XK_F7,
+        Key::F8 => XK_F8,
+        Key::F9 => XK_F9,
+        Key::F10 => XK_F10,
+        Key::F11 => XK_F11,
+        Key::F12 => XK_F12,
+        Key::F13 => XK_F13,
+        Key::F14 => XK_F14,
+        Key::F15 => XK_F15,
+        Key::Num0 => XK_KP_0,
+        Key::Num1 => XK_KP_1,
+        Key::Num2 => XK_KP_2,
+        Key::Num3 => XK_KP_3,
+        Key::Num4 => XK_KP_4,
+        Key::Num5 => XK_KP_5,
+        Key::Num6 => XK_KP_6,
+        Key::Num7 => XK_KP_7,
+        Key::Num8 => XK_KP_8,
+        Key::Num9 => XK_KP_9,
+        Key::NumAdd => XK_KP_Add,
+        Key::NumSubtract => XK_KP_Subtract,
+        Key::NumMultiply => XK_KP_Multiply,
+        Key::NumDivide => XK_KP_Divide,
+        Key::NumDecimal => XK_KP_Decimal,
+        Key::NumEnter => XK_KP_Enter,
+    };
+    keysym as xlib::KeySym
+}
+
+/// Modifier keys that select shift level `level` of a key, `None` if one
+/// of them is not mapped
+///
+/// Level 1 is Shift, level 2 AltGr (ISO_Level3_Shift), level 3 both.
+fn level_modifiers(
+    level: c_int,
+    shift: Option<xlib::KeyCode>,
+    level3: Option<xlib::KeyCode>,
+) -> Option<Vec<xlib::KeyCode>> {
+    match level {
+        0 => Some(Vec::new()),
+        1 => Some(vec![shift?]),
+        2 => Some(vec![level3?]),
+        3 => Some(vec![level3?, shift?]),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BGRX: PixelFormat = PixelFormat {
+        bits_per_pixel: 32,
+        lsb_first: true,
+        red_mask: 0x00ff_0000,
+        green_mask: 0x0000_ff00,
+        blue_mask: 0x0000_00ff,
+    };
+
+    /// DISPLAY is set, so tests can talk to an X server (e.g. `xvfb-run`)
+    fn has_display() -> bool {
+        std::env::var_os("DISPLAY").is_some()
+    }
+
+    #[test]
+    fn test_bgrx_to_bgr_skips_padding() {
+        // 2x2 pixels, 4 bytes of row padding
+        let data = [
+            1, 2, 3, 0, 4, 5, 6, 0, 9, 9, 9, 9, //
+            7, 8, 9, 0, 10, 11, 12, 0, 9, 9, 9, 9,
+        ];
+        let image = BGRX.to_bgr(&data, 12, Region::new(5, 5, 2, 2)).unwrap();
+
+        assert_eq!(image.region(), Region::new(5, 5, 2, 2));
+        assert_eq!(image.row(0), &[1, 2, 3, 4, 5, 6]);
+        assert_eq!(image.row(1), &[7, 8, 9, 10, 11, 12]);
+    }
+
+    #[test]
+    fn test_big_endian_rgb565() {
+        let format = PixelFormat {
+            bits_per_pixel: 16,
+            lsb_first: false,
+            red_mask: 0xf800,
+            green_mask: 0x07e0,
+            blue_mask: 0x001f,
+        };
+        // Pure red, then pure blue
+        let data = [0xf8, 0x00, 0x00, 0x1f];
+        let image = format.to_bgr(&data, 4, Region::new(0, 0, 2, 1)).unwrap();
+        assert_eq!(image.pixel(0, 0), Some([0, 0, 255]));
+        assert_eq!(image.pixel(1, 0), Some([255, 0, 0]));
+    }
+
+    #[test]
+    fn test_unsupported_depth() {
+        let format = PixelFormat {
+            bits_per_pixel: 8,
+            ..BGRX
+        };
+        assert!(format.to_bgr(&[0; 4], 4, Region::new(0, 0, 2, 2)).is_err());
+    }
+
+    #[test]
+    fn test_capture_root_window() {
+        if !has_display() {
+            return;
+        }
+        let display = XDisplay::open().unwrap();
+        let bounds = display.root_bounds().unwrap();
+        assert!(bounds.w > 0 && bounds.h > 0);
+
+        let image = display.capture(Region::new(0, 0, 16, 8)).unwrap();
+        assert_eq!((image.width(), image.height()), (16, 8));
+        assert_eq!(image.data().len(), 16 * 8 * 3);
+    }
+
+    #[test]
+    fn test_shm_capture_matches_xgetimage() {
+        if !has_display() {
+            return;
+        }
+        let region = Region::new(0, 0, 32, 24);
+        let mut shm = ShmCapture::new(region).unwrap();
+        shm.grab().unwrap();
+        let mut frame = ScreenImage::new(shm.area());
+        shm.copy_into(&mut frame).unwrap();
+
+        let direct = XDisplay::open().unwrap().capture(region).unwrap();
+        assert_eq!(frame, direct);
+    }
+
+    #[test]
+    fn test_char_to_keysym() {
+        assert_eq!(char_to_keysym('a'), 0x61);
+        assert_eq!(char_to_keysym('ä'), 0xe4);
+        assert_eq!(char_to_keysym('\n'), keysym::XK_Return as xlib::KeySym);
+        assert_eq!(char_to_keysym('€'), 0x0100_20ac);
+        assert_eq!(char_to_keysym('😀'), 0x0101_f600);
+    }
+
+    #[test]
+    fn test_key_to_keysym() {
+        assert_eq!(key_to_keysym(Key::F4), 0xffc1);
+        assert_eq!(key_to_keysym(Key::PageUp), 0xff55);
+        assert_eq!(key_to_keysym(Key::Char('t')), 0x74);
+    }
+
+    #[test]
+    fn test_level_modifiers() {
+        assert_eq!(level_modifiers(0, None, None), Some(vec![]));
+        assert_eq!(level_modifiers(1, Some(50), None), Some(vec![50]));
+        assert_eq!(level_modifiers(2, Some(50), None), None);
+        assert_eq!(level_modifiers(3, Some(50), Some(92)), Some(vec![92, 50]));
+    }
+
+    #[test]
+    fn test_monitors_cover_desktop() {
+        if !has_display() {
+            return;
+        }
+        let display = XDisplay::open().unwrap();
+        let desktop = display.root_bounds().unwrap();
+        let monitors = display.monitors().unwrap();
+
+        assert!(!monitors.is_empty());
+        for monitor in &monitors {
+            assert_eq!(desktop.intersection(&monitor.bounds), Some(monitor.bounds));
+        }
+    }
+
+    #[test]
+    fn test_capture_is_clipped_to_screen() {
+        if !has_display() {
+            return;
+        }
+        let display = XDisplay::open().unwrap();
+        let bounds = display.root_bounds().unwrap();
+
+        let image = display
+            .capture(Region::new(bounds.w - 10, bounds.h - 5, 40, 40))
+            .unwrap();
+        assert_eq!(
+            image.region(),
+            Region::new(bounds.w - 10, bounds.h - 5, 10, 5)
+        );
+
+        let outside = display.capture(Region::new(bounds.w + 1, 0, 10, 10));
+        assert!(matches!(outside.unwrap_err(), Error::InvalidRegion(_)));
+    }
+}
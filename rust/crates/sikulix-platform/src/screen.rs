@@ -1,18 +1,247 @@
 //! Screen capture functionality
 
-use sikulix_core::{Region, Result};
+use crate::capture_stream::CaptureStream;
+use sikulix_core::{Error, Region, Result, ScreenImage};
 
-/// Screen capture interface
+#[cfg(target_os = "linux")]
+use crate::platform::linux::XDisplay;
+#[cfg(target_os = "linux")]
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// A monitor (head) of the virtual desktop
+#[derive(Debug, Clone, PartialEq)]
+pub struct Monitor {
+    /// Position in `Screen::all`; 0 is the primary monitor
+    pub index: usize,
+
+    /// Output name reported by the platform (e.g. "DP-1"), if any
+    pub name: Option<String>,
+
+    /// Bounds in virtual-desktop coordinates
+    pub bounds: Region,
+
+    /// Whether this is the primary monitor
+    pub primary: bool,
+}
+
+/// One monitor of the desktop, like Java's `Screen(n)`
+///
+/// Regions passed to a screen are in virtual-desktop coordinates and are
+/// clipped to its monitor, so a screen never captures pixels of another
+/// head. Screens returned together by [`Screen::all`] share one display
+/// connection.
 pub struct Screen {
-    // Will be implemented in Phase 2
+    /// The monitor this screen is bound to
+    monitor: Monitor,
+
+    /// Connection used for capturing, one caller at a time
+    #[cfg(target_os = "linux")]
+    display: Arc<Mutex<XDisplay>>,
 }
 
 impl Screen {
+    /// Get the primary screen
+    ///
+    /// On Linux this opens the X display named by `DISPLAY`, which may be a
+    /// headless server such as Xvfb. Other platforms return
+    /// `Error::Platform` until they are supported.
     pub fn new() -> Result<Self> {
-        Ok(Self {})
+        Self::primary()
+    }
+
+    /// Get every monitor as a screen, the primary one first
+    #[cfg(target_os = "linux")]
+    pub fn all() -> Result<Vec<Screen>> {
+        let display = XDisplay::open()?;
+        let monitors = index_monitors(display.monitors()?);
+        let display = Arc::new(Mutex::new(display));
+
+        Ok(monitors
+            .into_iter()
+            .map(|monitor| Screen {
+                monitor,
+                display: Arc::clone(&display),
+            })
+            .collect())
+    }
+
+    /// Get every monitor as a screen, the primary one first
+    #[cfg(not(target_os = "linux"))]
+    pub fn all() -> Result<Vec<Screen>> {
+        Err(Error::Platform(
+            "Screen enumeration is not supported on this platform".to_string(),
+        ))
+    }
+
+    /// Get the primary screen, `Screen::at(0)`
+    pub fn primary() -> Result<Screen> {
+        Self::at(0)
+    }
+
+    /// Get the screen with the given index, as numbered by `Screen::all`
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidParameter` if there is no such monitor.
+    pub fn at(index: usize) -> Result<Screen> {
+        let mut screens = Self::all()?;
+        if index >= screens.len() {
+            return Err(Error::InvalidParameter(format!(
+                "Screen {} does not exist ({} screens)",
+                index,
+                screens.len()
+            )));
+        }
+        Ok(screens.swap_remove(index))
+    }
+
+    /// Describe the monitors of the desktop without binding screens to them
+    pub fn monitors() -> Result<Vec<Monitor>> {
+        Ok(Self::all()?.into_iter().map(|s| s.monitor).collect())
+    }
+
+    /// Get the index of this screen, 0 for the primary one
+    pub fn index(&self) -> usize {
+        self.monitor.index
+    }
+
+    /// Get the monitor this screen is bound to
+    pub fn monitor(&self) -> &Monitor {
+        &self.monitor
+    }
+
+    /// Get the bounds of this screen in virtual-desktop coordinates
+    pub fn bounds(&self) -> Region {
+        self.monitor.bounds
+    }
+
+    /// Clip `region` to this screen
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidRegion` if the region lies entirely outside
+    /// the screen.
+    pub fn clip(&self, region: Region) -> Result<Region> {
+        self.bounds().intersection(&region).ok_or_else(|| {
+            Error::InvalidRegion(format!(
+                "Region {:?} lies outside screen {} {:?}",
+                region,
+                self.index(),
+                self.bounds()
+            ))
+        })
     }
 
-    pub fn capture(&self, _region: Region) -> Result<Vec<u8>> {
-        unimplemented!("Screen capture - Phase 2")
+    /// Capture the whole screen
+    pub fn capture_screen(&self) -> Result<ScreenImage> {
+        self.capture(self.bounds())
+    }
+
+    /// Capture `region` of the screen as BGR pixels
+    ///
+    /// The region is clipped to the screen, so the returned image may be
+    /// smaller than requested; see `ScreenImage::region`.
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidRegion` if the region lies entirely outside
+    /// the screen.
+    #[cfg(target_os = "linux")]
+    pub fn capture(&self, region: Region) -> Result<ScreenImage> {
+        let area = self.clip(region)?;
+        self.display().capture(area)
+    }
+
+    /// Capture `region` of the screen as BGR pixels
+    #[cfg(not(target_os = "linux"))]
+    pub fn capture(&self, _region: Region) -> Result<ScreenImage> {
+        Err(Error::Platform(
+            "Screen capture is not supported on this platform".to_string(),
+        ))
+    }
+
+    /// Start capturing `region` continuously at `fps` frames per second
+    ///
+    /// The region is clipped to the screen. The stream uses its own
+    /// connection to the display, so it does not block other captures.
+    pub fn capture_stream(&self, region: Region, fps: f64) -> Result<CaptureStream> {
+        CaptureStream::new(self.clip(region)?, fps)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn display(&self) -> MutexGuard<'_, XDisplay> {
+        // A panic while capturing leaves the connection itself usable
+        self.display.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Number monitors like the Java API: the primary one first, then the
+/// others from left to right and top to bottom
+///
+/// When the platform flags no monitor as primary, the first one reported
+/// becomes the primary monitor.
+fn index_monitors(mut monitors: Vec<Monitor>) -> Vec<Monitor> {
+    if !monitors.iter().any(|m| m.primary) {
+        if let Some(first) = monitors.first_mut() {
+            first.primary = true;
+        }
+    }
+
+    monitors.sort_by_key(|m| (!m.primary, m.bounds.x, m.bounds.y));
+    for (index, monitor) in monitors.iter_mut().enumerate() {
+        monitor.index = index;
+    }
+    monitors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(x: i32, primary: bool) -> Monitor {
+        Monitor {
+            index: 99,
+            name: None,
+            bounds: Region::new(x, 0, 100, 100),
+            primary,
+        }
+    }
+
+    #[test]
+    fn test_primary_monitor_comes_first() {
+        let monitors = index_monitors(vec![
+            monitor(200, false),
+            monitor(100, true),
+            monitor(0, false),
+        ]);
+
+        let order: Vec<_> = monitors.iter().map(|m| (m.index, m.bounds.x)).collect();
+        assert_eq!(order, vec![(0, 100), (1, 0), (2, 200)]);
+    }
+
+    #[test]
+    fn test_first_monitor_is_primary_by_default() {
+        let monitors = index_monitors(vec![monitor(100, false), monitor(0, false)]);
+        assert!(monitors[0].primary);
+        assert_eq!(monitors[0].bounds.x, 100);
+        assert!(!monitors[1].primary);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_screens_are_clipped_to_their_monitor() {
+        if std::env::var_os("DISPLAY").is_none() {
+            return;
+        }
+        for screen in Screen::all().unwrap() {
+            let bounds = screen.bounds();
+            let image = screen
+                .capture(Region::new(bounds.x - 10, bounds.y - 10, 30, 20))
+                .unwrap();
+            assert_eq!(image.region(), Region::new(bounds.x, bounds.y, 20, 10));
+        }
+
+        let count = Screen::monitors().unwrap().len();
+        assert!(matches!(
+            Screen::at(count).err().unwrap(),
+            Error::InvalidParameter(_)
+        ));
     }
 }
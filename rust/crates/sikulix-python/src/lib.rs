@@ -2,18 +2,27 @@
 
 use pyo3::prelude::*;
 
+mod py_errors;
+mod py_image_path;
 mod py_region;
 mod py_location;
+mod py_virtual_desktop;
 
 use py_location::{PyLocation, PyOffset};
 use py_region::PyRegion;
+use py_errors::FindFailed;
+use py_image_path::PyImagePath;
+use py_virtual_desktop::PyVirtualDesktop;
 
 /// SikuliX Python module
 #[pymodule]
-fn sikulix(_py: Python, m: &PyModule) -> PyResult<()> {
+fn sikulix(py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<PyLocation>()?;
     m.add_class::<PyOffset>()?;
     m.add_class::<PyRegion>()?;
+    m.add_class::<PyImagePath>()?;
+    m.add_class::<PyVirtualDesktop>()?;
+    m.add("FindFailed", py.get_type::<FindFailed>())?;
 
     Ok(())
 }
@@ -0,0 +1,29 @@
+//! Mapping of SikuliX errors to Python exceptions
+
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyFileNotFoundError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use sikulix_core::Error;
+
+create_exception!(
+    sikulix,
+    FindFailed,
+    PyException,
+    "An image was not found on the screen"
+);
+
+/// Map a SikuliX error to a Python exception
+pub(crate) fn to_py_err(error: Error) -> PyErr {
+    match error {
+        Error::PatternNotFound(_)
+        | Error::Timeout(_)
+        | Error::FindTimedOut(_)
+        | Error::FindSkipped(_)
+        | Error::FindAborted(_) => FindFailed::new_err(error.to_string()),
+        Error::ImageNotFound(_) => PyFileNotFoundError::new_err(error.to_string()),
+        Error::InvalidParameter(_) | Error::InvalidRegion(_) => {
+            PyValueError::new_err(error.to_string())
+        }
+        _ => PyRuntimeError::new_err(error.to_string()),
+    }
+}
@@ -0,0 +1,55 @@
+//! Python bindings for the global ImagePath
+
+use pyo3::prelude::*;
+use sikulix_core::ImagePath;
+use std::path::PathBuf;
+
+use crate::py_errors::to_py_err;
+
+/// Directories image names are looked up in, shared by the whole process
+#[pyclass(name = "ImagePath")]
+pub struct PyImagePath;
+
+#[pymethods]
+impl PyImagePath {
+    /// Set the directory searched first, usually the script's folder
+    #[staticmethod]
+    fn set_bundle_path(path: PathBuf) {
+        ImagePath::global_mut().set_bundle_path(path);
+    }
+
+    #[staticmethod]
+    fn get_bundle_path() -> Option<PathBuf> {
+        ImagePath::global().bundle_path().map(PathBuf::from)
+    }
+
+    /// Append a directory to search after the bundle path
+    #[staticmethod]
+    fn add(path: PathBuf) {
+        ImagePath::global_mut().add(path);
+    }
+
+    /// Remove a directory, returning whether it was listed
+    #[staticmethod]
+    fn remove(path: PathBuf) -> bool {
+        ImagePath::global_mut().remove(path)
+    }
+
+    /// Forget the bundle path and all added directories
+    #[staticmethod]
+    fn reset() {
+        ImagePath::global_mut().reset();
+    }
+
+    #[staticmethod]
+    fn get_paths() -> Vec<PathBuf> {
+        ImagePath::global().paths().to_vec()
+    }
+
+    /// Find an image file, raising FileNotFoundError with the searched
+    /// locations if it does not exist
+    #[staticmethod]
+    fn resolve(name: PathBuf) -> PyResult<PathBuf> {
+        ImagePath::global().resolve(name).map_err(to_py_err)
+    }
+}
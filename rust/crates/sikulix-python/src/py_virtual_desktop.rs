@@ -0,0 +1,229 @@
+//! Python bindings for the in-memory VirtualDesktop
+
+use pyo3::prelude::*;
+use pyo3::types::PyTuple;
+use sikulix_core::{
+    AutoRegion, Backends, ImagePath, InputEvent, Location, MouseButton, Region, SceneChange,
+    ScreenImage, Trigger, VirtualDesktop,
+};
+use sikulix_vision::{ImageLoader, VisionFinder};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::py_errors::to_py_err;
+use crate::py_location::PyLocation;
+use crate::py_region::PyRegion;
+
+/// A fake screen with scripted reactions and recorded input, for tests
+#[pyclass(name = "VirtualDesktop")]
+pub struct PyVirtualDesktop {
+    desktop: Arc<VirtualDesktop>,
+    screen: AutoRegion,
+}
+
+#[pymethods]
+impl PyVirtualDesktop {
+    #[new]
+    #[pyo3(signature = (width, height, background = (0, 0, 0)))]
+    fn new(width: i32, height: i32, background: (u8, u8, u8)) -> Self {
+        let (b, g, r) = background;
+        let desktop = Arc::new(VirtualDesktop::new(width, height).with_background([b, g, r]));
+        let backends = Backends::from_shared(
+            desktop.clone(),
+            desktop.clone(),
+            Arc::new(VisionFinder::new()),
+        );
+        let screen = backends.region(Region::new(0, 0, width.max(0), height.max(0)));
+        Self { desktop, screen }
+    }
+
+    /// Place the image file at `path` with its top-left corner at `location`
+    ///
+    /// Relative paths are looked up in the `ImagePath`.
+    #[pyo3(signature = (name, path, location, visible = true))]
+    fn add_image(
+        &self,
+        name: &str,
+        path: &str,
+        location: &PyLocation,
+        visible: bool,
+    ) -> PyResult<PyRegion> {
+        let path = ImagePath::global().resolve(path).map_err(to_py_err)?;
+        let mat = ImageLoader::load_from_file(path, true).map_err(to_py_err)?;
+        let image = ScreenImage::try_from(&mat).map_err(to_py_err)?;
+        let region = if visible {
+            self.desktop.add(name, location.inner(), image)
+        } else {
+            self.desktop.add_hidden(name, location.inner(), image)
+        };
+        Ok(PyRegion::from_inner(region))
+    }
+
+    /// Fill `region` with a `(b, g, r)` color
+    #[pyo3(signature = (name, region, color, visible = true))]
+    fn fill(
+        &self,
+        name: &str,
+        region: &PyRegion,
+        color: (u8, u8, u8),
+        visible: bool,
+    ) -> PyResult<PyRegion> {
+        let (b, g, r) = color;
+        let region = self.desktop.fill(name, region.inner(), [b, g, r]);
+        if !visible {
+            self.desktop.hide(name).map_err(to_py_err)?;
+        }
+        Ok(PyRegion::from_inner(region))
+    }
+
+    fn show(&self, name: &str) -> PyResult<()> {
+        self.desktop.show(name).map_err(to_py_err)
+    }
+
+    fn hide(&self, name: &str) -> PyResult<()> {
+        self.desktop.hide(name).map_err(to_py_err)
+    }
+
+    fn is_visible(&self, name: &str) -> bool {
+        self.desktop.is_visible(name)
+    }
+
+    /// Show and hide elements when the left button is clicked inside `region`
+    #[pyo3(signature = (region, show = Vec::new(), hide = Vec::new()))]
+    fn on_click(&self, region: &PyRegion, show: Vec<String>, hide: Vec<String>) {
+        self.desktop.on_click(region.inner(), changes(show, hide));
+    }
+
+    /// Show and hide elements when exactly `text` is typed
+    #[pyo3(signature = (text, show = Vec::new(), hide = Vec::new()))]
+    fn on_type(&self, text: &str, show: Vec<String>, hide: Vec<String>) {
+        self.desktop
+            .react(Trigger::Typed(text.to_string()), changes(show, hide));
+    }
+
+    /// Get the recorded input as tuples like `("move", x, y)`,
+    /// `("down", "left", x, y)`, `("up", "left", x, y)` or `("type", text)`
+    fn events(&self, py: Python<'_>) -> Vec<Py<PyTuple>> {
+        self.desktop
+            .events()
+            .into_iter()
+            .map(|event| match event {
+                InputEvent::Move(at) => {
+                    PyTuple::new(py, ["move".into_py(py), at.x.into_py(py), at.y.into_py(py)])
+                }
+                InputEvent::ButtonDown(button, at) => PyTuple::new(
+                    py,
+                    [
+                        "down".into_py(py),
+                        button_name(button).into_py(py),
+                        at.x.into_py(py),
+                        at.y.into_py(py),
+                    ],
+                ),
+                InputEvent::ButtonUp(button, at) => PyTuple::new(
+                    py,
+                    [
+                        "up".into_py(py),
+                        button_name(button).into_py(py),
+                        at.x.into_py(py),
+                        at.y.into_py(py),
+                    ],
+                ),
+                InputEvent::Type(text) => PyTuple::new(py, ["type".into_py(py), text.into_py(py)]),
+            })
+            .map(Into::into)
+            .collect()
+    }
+
+    fn clear_events(&self) {
+        self.desktop.take_events();
+    }
+
+    fn pointer(&self) -> PyLocation {
+        PyLocation::from_inner(self.desktop.pointer())
+    }
+
+    /// Find the image file at `path` on the desktop
+    fn find(&self, path: &str) -> PyResult<PyRegion> {
+        let found = self.screen.find(path).map_err(to_py_err)?;
+        Ok(PyRegion::from_inner(found.region))
+    }
+
+    /// Check whether the image file at `path` appears on the desktop
+    /// within `timeout` seconds, by default the auto wait timeout
+    #[pyo3(signature = (path, timeout = None))]
+    fn exists(&self, path: &str, timeout: Option<f64>) -> PyResult<Option<PyRegion>> {
+        let timeout = timeout.map_or(self.screen.auto_wait_timeout(), seconds);
+        let found = self
+            .screen
+            .exists_within(path, timeout)
+            .map_err(to_py_err)?;
+        Ok(found.map(|m| PyRegion::from_inner(m.region)))
+    }
+
+    /// Wait up to `timeout` seconds for the image file at `path`
+    fn wait(&self, path: &str, timeout: f64) -> PyResult<PyRegion> {
+        let found = self
+            .screen
+            .wait(path, seconds(timeout))
+            .map_err(to_py_err)?;
+        Ok(PyRegion::from_inner(found.region))
+    }
+
+    /// Wait up to `timeout` seconds for the image file at `path` to vanish
+    fn wait_vanish(&self, path: &str, timeout: f64) -> PyResult<bool> {
+        self.screen
+            .wait_vanish(path, seconds(timeout))
+            .map_err(to_py_err)
+    }
+
+    /// Click an image file path or a Location
+    fn click(&self, target: &PyAny) -> PyResult<()> {
+        if let Ok(location) = target.extract::<PyLocation>() {
+            return self.click_at(location.inner()).map_err(to_py_err);
+        }
+        let path: &str = target.extract()?;
+        self.screen.click(path).map(|_| ()).map_err(to_py_err)
+    }
+
+    #[pyo3(name = "type")]
+    fn type_text(&self, text: &str) -> PyResult<()> {
+        self.screen.type_text(text).map_err(to_py_err)
+    }
+
+    fn __repr__(&self) -> String {
+        let region = self.screen.region();
+        format!("VirtualDesktop({}, {})", region.w, region.h)
+    }
+}
+
+impl PyVirtualDesktop {
+    fn click_at(&self, location: Location) -> sikulix_core::Result<()> {
+        let input = self.screen.backends().input();
+        input.move_to(location)?;
+        input.click(MouseButton::Left)
+    }
+}
+
+/// Convert seconds from Python, treating negative values as zero and
+/// values too large for a `Duration`, like `float('inf')`, as forever
+fn seconds(secs: f64) -> Duration {
+    if secs.is_nan() || secs <= 0.0 {
+        return Duration::ZERO;
+    }
+    Duration::try_from_secs_f64(secs).unwrap_or(Duration::MAX)
+}
+
+fn changes(show: Vec<String>, hide: Vec<String>) -> Vec<SceneChange> {
+    let shown = show.into_iter().map(SceneChange::Show);
+    let hidden = hide.into_iter().map(SceneChange::Hide);
+    hidden.chain(shown).collect()
+}
+
+fn button_name(button: MouseButton) -> &'static str {
+    match button {
+        MouseButton::Left => "left",
+        MouseButton::Middle => "middle",
+        MouseButton::Right => "right",
+    }
+}
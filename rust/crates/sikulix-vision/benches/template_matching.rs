@@ -1,15 +1,73 @@
-// Benchmark for template matching - will be implemented in Phase 1
+// Benchmark for template matching: full resolution against coarse-to-fine
+// search of a 200x150 pattern on a 4K screenshot, for a pattern that is
+// on the screen and for one that is not
 
 use criterion::{criterion_group, criterion_main, Criterion};
+use sikulix_core::{Image, Pattern, Region};
+use sikulix_vision::{Finder, Interpolation, MatWrapper, PixelLayout, PyramidSearch, Template};
 
-fn bench_placeholder(c: &mut Criterion) {
-    c.bench_function("placeholder", |b| {
-        b.iter(|| {
-            // Placeholder - actual benchmarks in Phase 1
-            1 + 1
-        });
+const SCREEN: (i32, i32) = (3840, 2160);
+
+/// Screenshot-like image: deterministic noise upscaled into smooth areas
+fn screen(seed: u32) -> MatWrapper {
+    let (w, h) = (SCREEN.0 / 8, SCREEN.1 / 8);
+    let mut state = seed;
+    let data: Vec<u8> = (0..w * h * 3)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (state >> 16) as u8
+        })
+        .collect();
+    MatWrapper::from_raw(PixelLayout::Bgr, w as u32, h as u32, w as usize * 3, &data)
+        .unwrap()
+        .resize(SCREEN.0, SCREEN.1, Interpolation::Cubic)
+        .unwrap()
+}
+
+fn bench_find(c: &mut Criterion) {
+    let other = screen(8);
+    let screen = screen(7);
+    let target = Region::new(2517, 1303, 200, 150);
+    let template = Template::new(screen.view(target).unwrap().clone_mat().unwrap());
+    let missing = Template::new(other.view(target).unwrap().clone_mat().unwrap());
+    let pattern = Pattern::new(Image::from_path("pattern.png"));
+    let region = Region::new(0, 0, SCREEN.0, SCREEN.1);
+
+    let full = Finder::new(screen.clone_mat().unwrap()).pyramid_search(None);
+    let pyramid = Finder::new(screen).pyramid_search(Some(PyramidSearch::default()));
+
+    let expected = full.find_template(region, &template, &pattern).unwrap();
+    let found = pyramid.find_template(region, &template, &pattern).unwrap();
+    assert_eq!(
+        found.map(|m| m.region),
+        expected.map(|m| m.region),
+        "pyramid search must agree with the full search"
+    );
+    assert!(full
+        .find_template(region, &missing, &pattern)
+        .unwrap()
+        .is_none());
+    assert!(pyramid
+        .find_template(region, &missing, &pattern)
+        .unwrap()
+        .is_none());
+
+    let mut group = c.benchmark_group("find_200x150_in_4k");
+    group.sample_size(10);
+    group.bench_function("full", |b| {
+        b.iter(|| full.find_template(region, &template, &pattern).unwrap())
+    });
+    group.bench_function("pyramid", |b| {
+        b.iter(|| pyramid.find_template(region, &template, &pattern).unwrap())
+    });
+    group.bench_function("full_missing", |b| {
+        b.iter(|| full.find_template(region, &missing, &pattern).unwrap())
+    });
+    group.bench_function("pyramid_missing", |b| {
+        b.iter(|| pyramid.find_template(region, &missing, &pattern).unwrap())
     });
+    group.finish();
 }
 
-criterion_group!(benches, bench_placeholder);
+criterion_group!(benches, bench_find);
 criterion_main!(benches);
@@ -0,0 +1,87 @@
+//! OpenCV template matching as a `sikulix_core::FinderBackend`
+
+use crate::finder::Finder;
+use crate::mat_wrapper::MatWrapper;
+use sikulix_core::{FinderBackend, Match, Matches, Offset, Pattern, Result, ScreenImage};
+
+/// Finds patterns in screen captures with [`Finder`]
+#[derive(Debug, Clone, Default)]
+pub struct VisionFinder {
+    /// Largest tolerated overlap between two `find_all` matches
+    max_overlap: f64,
+}
+
+impl VisionFinder {
+    /// Create a finder backend with the default `Finder` settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the largest overlap tolerated between two `find_all` matches,
+    /// see [`Finder::max_overlap`]
+    pub fn max_overlap(mut self, ratio: f64) -> Self {
+        self.max_overlap = ratio;
+        self
+    }
+
+    fn finder(&self, image: &ScreenImage) -> Result<Finder> {
+        Ok(Finder::new(MatWrapper::try_from(image)?).max_overlap(self.max_overlap))
+    }
+}
+
+impl FinderBackend for VisionFinder {
+    fn find(&self, image: &ScreenImage, pattern: &Pattern) -> Result<Option<Match>> {
+        let finder = self.finder(image)?;
+        let found = finder.find(finder.bounds()?, pattern)?;
+        Ok(found.map(|m| to_screen(m, image)))
+    }
+
+    fn find_all(&self, image: &ScreenImage, pattern: &Pattern) -> Result<Matches> {
+        let finder = self.finder(image)?;
+        let found = finder.find_all(finder.bounds()?, pattern)?;
+        Ok(found.into_iter().map(|m| to_screen(m, image)).collect())
+    }
+}
+
+/// Move a match from image pixel coordinates to screen coordinates
+fn to_screen(mut found: Match, image: &ScreenImage) -> Match {
+    let origin = image.region();
+    found.region = found.region.offset(Offset::new(origin.x, origin.y));
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{noise_mat, png_fixture};
+    use opencv::core::Rect;
+    use opencv::prelude::*;
+    use sikulix_core::{Image, Region};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_matches_are_in_screen_coordinates() {
+        let dir = TempDir::new().unwrap();
+        let screen = noise_mat(120, 80, 7);
+        let crop = screen
+            .roi(Rect::new(30, 20, 16, 12))
+            .unwrap()
+            .try_clone()
+            .unwrap();
+        let (needle_path, _) = png_fixture(&dir, "needle.png", &crop);
+
+        // The capture was taken at (500, 300) on the desktop
+        let data = screen.data_bytes().unwrap().to_vec();
+        let capture = ScreenImage::from_bgr(Region::new(500, 300, 120, 80), 360, data).unwrap();
+
+        let pattern = Pattern::new(Image::from_path(needle_path.to_str().unwrap()));
+        let found = VisionFinder::new()
+            .find(&capture, &pattern)
+            .unwrap()
+            .expect("pattern should be found");
+        assert_eq!(found.region, Region::new(530, 320, 16, 12));
+
+        let all = VisionFinder::new().find_all(&capture, &pattern).unwrap();
+        assert_eq!(all.best().unwrap().region, found.region);
+    }
+}
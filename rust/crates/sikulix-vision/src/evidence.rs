@@ -0,0 +1,153 @@
+//! Saving screenshots and diff images of failed searches
+
+use crate::image_loader::ImageLoader;
+use crate::image_writer::ImageWriter;
+use crate::mat_wrapper::{to_bgr, MatWrapper};
+use opencv::core::{absdiff, hconcat, Mat, Rect, Size, Vector};
+use opencv::imgproc::{resize, INTER_LINEAR};
+use opencv::prelude::*;
+use sikulix_core::{
+    Error, Evidence, EvidenceWriter, ImagePath, Match, Pattern, Result, ScreenImage,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Writes evidence of failed searches as PNG files into a directory
+///
+/// Each failure saves `NNN-<pattern>-screen.png`, the searched screenshot,
+/// and, if there was a candidate, `NNN-<pattern>-diff.png` showing the
+/// pattern, the best candidate and their absolute difference side by side.
+/// `NNN` counts the failures of this writer.
+#[derive(Debug)]
+pub struct EvidenceDir {
+    dir: PathBuf,
+    saved: AtomicUsize,
+}
+
+impl EvidenceDir {
+    /// Save evidence into `dir`, which is created when first needed
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            saved: AtomicUsize::new(0),
+        }
+    }
+
+    /// Get the directory evidence is saved to
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl EvidenceWriter for EvidenceDir {
+    fn save(
+        &self,
+        pattern: &Pattern,
+        screenshot: &ScreenImage,
+        best: Option<&Match>,
+    ) -> Result<Evidence> {
+        fs::create_dir_all(&self.dir)?;
+        let number = self.saved.fetch_add(1, Ordering::Relaxed) + 1;
+        let name = pattern
+            .image
+            .path_buf()
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "pattern".to_string());
+        let prefix = format!("{:03}-{}", number, name);
+
+        let writer = ImageWriter::new();
+        let haystack = MatWrapper::try_from(screenshot)?;
+        let screenshot_path = self.dir.join(format!("{}-screen.png", prefix));
+        writer.save(&haystack, &screenshot_path)?;
+
+        let diff = match best {
+            Some(best) => {
+                let path = self.dir.join(format!("{}-diff.png", prefix));
+                let image = side_by_side(pattern, &haystack, screenshot, best)?;
+                writer.save(&MatWrapper::new(image), &path)?;
+                Some(path)
+            }
+            None => None,
+        };
+
+        Ok(Evidence {
+            screenshot: screenshot_path,
+            diff,
+        })
+    }
+}
+
+/// Put the pattern, the candidate scaled to the pattern's size and their
+/// absolute difference next to each other
+fn side_by_side(
+    pattern: &Pattern,
+    haystack: &MatWrapper,
+    screenshot: &ScreenImage,
+    best: &Match,
+) -> Result<Mat> {
+    let needle_path = ImagePath::global().resolve(pattern.image.path_buf())?;
+    let needle = to_bgr(ImageLoader::load_from_file(needle_path, false)?.as_mat())?;
+
+    let origin = screenshot.region();
+    let area = best.region.intersection(&origin).ok_or_else(|| {
+        Error::InvalidRegion(format!(
+            "Candidate {:?} lies outside the screenshot {:?}",
+            best.region, origin
+        ))
+    })?;
+    let rect = Rect::new(area.x - origin.x, area.y - origin.y, area.w, area.h);
+    let crop = haystack.as_mat().roi(rect)?.try_clone()?;
+
+    let mut candidate = Mat::default();
+    resize(
+        &crop,
+        &mut candidate,
+        Size::new(needle.cols(), needle.rows()),
+        0.0,
+        0.0,
+        INTER_LINEAR,
+    )?;
+
+    let mut difference = Mat::default();
+    absdiff(&needle, &candidate, &mut difference)?;
+
+    let parts = Vector::<Mat>::from_iter([needle, candidate, difference]);
+    let mut joined = Mat::default();
+    hconcat(&parts, &mut joined)?;
+    Ok(joined)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{noise_mat, png_fixture};
+    use sikulix_core::{Image, Region};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_saves_screenshot_and_diff() {
+        let dir = TempDir::new().unwrap();
+        let (needle_path, _) = png_fixture(&dir, "button.png", &noise_mat(16, 12, 3));
+        let pattern = Pattern::new(Image::from_path(needle_path.to_str().unwrap()));
+
+        let screen = noise_mat(60, 40, 9);
+        let data = screen.data_bytes().unwrap().to_vec();
+        let capture = ScreenImage::from_bgr(Region::new(100, 50, 60, 40), 180, data).unwrap();
+        let best = Match::new(Region::new(110, 60, 16, 12), 0.4);
+
+        let writer = EvidenceDir::new(dir.path().join("evidence"));
+        let evidence = writer.save(&pattern, &capture, Some(&best)).unwrap();
+        assert!(evidence.screenshot.ends_with("001-button-screen.png"));
+        let saved = ImageLoader::load_from_file(&evidence.screenshot, true).unwrap();
+        assert_eq!(saved.size().unwrap(), (60, 40));
+
+        let diff = ImageLoader::load_from_file(evidence.diff.unwrap(), true).unwrap();
+        assert_eq!(diff.size().unwrap(), (48, 12));
+
+        let evidence = writer.save(&pattern, &capture, None).unwrap();
+        assert!(evidence.screenshot.ends_with("002-button-screen.png"));
+        assert!(evidence.diff.is_none());
+    }
+}
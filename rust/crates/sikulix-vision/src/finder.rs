@@ -1,18 +1,993 @@
 //! Template matching and pattern finding
 
-use sikulix_core::{Error, Match, Pattern, Region, Result};
+use crate::mat_wrapper::{to_bgr, MatWrapper};
+use crate::matcher::{MatchPeak, TemplateMatcher};
+use crate::template::Template;
+use opencv::core::{Mat, ToInputArray};
+use opencv::prelude::*;
+use sikulix_core::{Match, MatchMethod, Matches, Pattern, Region, Result};
+use std::sync::OnceLock;
+use tracing::debug;
+
+/// Settings of the coarse-to-fine search done by [`Finder::find`]
+///
+/// The template and the searched image are first shrunk by a Gaussian
+/// pyramid and matched at low resolution. The coarse candidates scoring
+/// within `score_tolerance` of the best coarse score are then matched at
+/// full resolution, in small areas around them, so their scores are exact.
+///
+/// A match can only be missed when its coarse score falls more than
+/// `score_tolerance` below the best coarse candidate. Like the Java
+/// `Finder2`, the search falls back to full resolution when no refined
+/// candidate reaches the pattern's similarity, so a coarse pass never
+/// turns a match into `None`. Shrinking can lower the score of textured
+/// patterns far below `score_tolerance`, which the fallback also covers;
+/// the price is that a search for a missing pattern costs the coarse pass
+/// on top of the full one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PyramidSearch {
+    /// Most times the images are halved for the coarse pass
+    pub max_levels: u32,
+
+    /// Smallest side, in pixels, of the template at the coarse level, like
+    /// Java's `Settings.MinTargetSize`; smaller templates use fewer levels
+    /// or none at all
+    pub min_target_size: i32,
+
+    /// How far below the best coarse score a candidate may score and still
+    /// be refined
+    pub score_tolerance: f64,
+
+    /// Most coarse candidates refined at full resolution
+    pub max_candidates: usize,
+}
+
+impl Default for PyramidSearch {
+    fn default() -> Self {
+        Self {
+            max_levels: 3,
+            min_target_size: 12,
+            score_tolerance: 0.1,
+            max_candidates: 8,
+        }
+    }
+}
+
+impl PyramidSearch {
+    /// Get the number of halvings for a `width` x `height` template
+    ///
+    /// Zero means the template is too small for a coarse pass.
+    pub fn levels_for(&self, width: i32, height: i32) -> u32 {
+        let side = width.min(height);
+        let mut levels = 0;
+        while levels < self.max_levels && (side >> (levels + 1)) >= self.min_target_size {
+            levels += 1;
+        }
+        levels
+    }
+}
 
 /// Finds patterns in images using template matching
+///
+/// A `Finder` owns the image that is searched (the haystack, usually a
+/// screenshot). Search regions and match results are expressed in the
+/// haystack's pixel coordinates.
 pub struct Finder {
-    // Will be implemented in Phase 1
+    /// The image searched by this finder
+    image: MatWrapper,
+
+    /// Largest tolerated overlap between two matches of `find_all`
+    max_overlap: f64,
+
+    /// Coarse-to-fine settings of `find`, or `None` to always match at
+    /// full resolution
+    pyramid: Option<PyramidSearch>,
+
+    /// Halved copies of `image`, built on the first coarse search
+    levels: OnceLock<Vec<MatWrapper>>,
 }
 
 impl Finder {
-    pub fn new() -> Self {
-        Self {}
+    /// Create a finder that searches inside `image`
+    pub fn new(image: MatWrapper) -> Self {
+        Self {
+            image,
+            max_overlap: 0.0,
+            pyramid: Some(PyramidSearch::default()),
+            levels: OnceLock::new(),
+        }
+    }
+
+    /// Set the largest overlap tolerated between two `find_all` matches
+    ///
+    /// The overlap is the intersection area divided by the smaller match
+    /// area. With the default of 0.0, any candidate overlapping a better
+    /// match is dropped, as in the Java implementation.
+    pub fn max_overlap(mut self, ratio: f64) -> Self {
+        self.max_overlap = ratio.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set how `find` searches coarse to fine, or `None` to always match
+    /// at full resolution
+    ///
+    /// The default is [`PyramidSearch::default`]. `find_all` always
+    /// matches at full resolution.
+    pub fn pyramid_search(mut self, settings: Option<PyramidSearch>) -> Self {
+        self.pyramid = settings;
+        self.levels = OnceLock::new();
+        self
+    }
+
+    /// Get the image searched by this finder
+    pub fn image(&self) -> &MatWrapper {
+        &self.image
+    }
+
+    /// Get the bounds of the searched image as a region at the origin
+    pub fn bounds(&self) -> Result<Region> {
+        self.image.bounds()
+    }
+
+    /// Find the best match of `pattern` inside `region`
+    ///
+    /// The pattern image (and mask, if any) is loaded from disk. When the
+    /// pattern defines a scale search, the template is matched at every
+    /// scale and the best scoring one wins; its factor is reported as
+    /// `Match::scale`. Masked-out template pixels do not affect the score.
+    /// Unmasked templates large enough are searched coarse to fine, see
+    /// [`PyramidSearch`].
+    ///
+    /// Returns `Ok(None)` when the best candidate scores below
+    /// `Pattern::similarity` or the pattern does not fit into the search region.
+    pub fn find(&self, region: Region, pattern: &Pattern) -> Result<Option<Match>> {
+        let template = Template::load(pattern)?;
+        self.find_template(region, &template, pattern)
+    }
+
+    /// Find the best match of an already loaded template inside `region`
+    ///
+    /// `pattern` supplies the similarity threshold, match method, scales
+    /// and target offset; its image path and mask are not used.
+    pub fn find_template(
+        &self,
+        region: Region,
+        template: &Template,
+        pattern: &Pattern,
+    ) -> Result<Option<Match>> {
+        let search = self.clip_region(region)?;
+
+        let mut best: Option<Match> = None;
+        for_each_scale(template, pattern, |scale, scaled| {
+            let Some(found) = self.best_match(search, scaled, pattern, scale)? else {
+                return Ok(());
+            };
+            let is_better = match &best {
+                Some(b) => found.score > b.score,
+                None => true,
+            };
+            if is_better {
+                best = Some(found);
+            }
+            Ok(())
+        })?;
+
+        let Some(best) = best else {
+            return Ok(None);
+        };
+        debug!(
+            "Best candidate for {} at ({}, {}) scale {:.2} score {:.4} (required {:.2})",
+            pattern.image.path(),
+            best.region.x,
+            best.region.y,
+            best.scale,
+            best.score,
+            pattern.similarity
+        );
+
+        if best.score < pattern.similarity {
+            return Ok(None);
+        }
+        Ok(Some(best))
+    }
+
+    /// Find all non-overlapping matches of `pattern` inside `region`
+    ///
+    /// Like the Java `Region.findAll`, every candidate scoring at least
+    /// `Pattern::similarity` is reported once; weaker candidates overlapping
+    /// a better one are suppressed (see [`Finder::max_overlap`]). The
+    /// matches are returned sorted by score, best first.
+    pub fn find_all(&self, region: Region, pattern: &Pattern) -> Result<Matches> {
+        let template = Template::load(pattern)?;
+        self.find_all_template(region, &template, pattern)
+    }
+
+    /// Find all non-overlapping matches of an already loaded template
+    pub fn find_all_template(
+        &self,
+        region: Region,
+        template: &Template,
+        pattern: &Pattern,
+    ) -> Result<Matches> {
+        let search = self.clip_region(region)?;
+
+        let mut candidates: Vec<Match> = Vec::new();
+        for_each_scale(template, pattern, |scale, scaled| {
+            let Some(result) = similarity_map(&self.image, search, scaled, pattern.method)? else {
+                return Ok(());
+            };
+            let (tw, th) = scaled.size()?;
+            candidates.extend(
+                TemplateMatcher::peaks_above(&result, pattern.similarity as f64)?
+                    .into_iter()
+                    .map(|peak| to_match(search, peak, tw, th, scale, pattern)),
+            );
+            Ok(())
+        })?;
+        let candidate_count = candidates.len();
+
+        let matches = suppress_overlaps(candidates, self.max_overlap);
+        debug!(
+            "Found {} matches for {} ({} candidates before suppression)",
+            matches.len(),
+            pattern.image.path(),
+            candidate_count
+        );
+
+        Ok(Matches::new(matches))
+    }
+
+    /// Find the best position of one template inside `search`, coarse to
+    /// fine when that applies
+    fn best_match(
+        &self,
+        search: Region,
+        template: &Template,
+        pattern: &Pattern,
+        scale: f32,
+    ) -> Result<Option<Match>> {
+        if let Some(found) = self.coarse_to_fine(search, template, pattern, scale)? {
+            if found.score >= pattern.similarity {
+                return Ok(Some(found));
+            }
+            debug!(
+                "Coarse pass for {} reached only {:.4}, searching at full resolution",
+                pattern.image.path(),
+                found.score
+            );
+        }
+        self.full_match(search, template, pattern, scale)
+    }
+
+    /// Match `template` at full resolution over all of `search`
+    fn full_match(
+        &self,
+        search: Region,
+        template: &Template,
+        pattern: &Pattern,
+        scale: f32,
+    ) -> Result<Option<Match>> {
+        let Some(result) = similarity_map(&self.image, search, template, pattern.method)? else {
+            return Ok(None);
+        };
+        let peak = TemplateMatcher::best_peak(&result)?;
+        let (tw, th) = template.size()?;
+        Ok(Some(to_match(search, peak, tw, th, scale, pattern)))
+    }
+
+    /// Match shrunk images, then refine the best candidates at full
+    /// resolution
+    ///
+    /// Returns `None` when the template is masked, too small for a coarse
+    /// pass, or the search area too small for the shrunk template.
+    fn coarse_to_fine(
+        &self,
+        search: Region,
+        template: &Template,
+        pattern: &Pattern,
+        scale: f32,
+    ) -> Result<Option<Match>> {
+        let Some(settings) = self.pyramid else {
+            return Ok(None);
+        };
+        if template.mask().is_some() {
+            return Ok(None);
+        }
+        let (tw, th) = template.size()?;
+        let levels = settings.levels_for(tw, th);
+        if levels == 0 {
+            return Ok(None);
+        }
+        let Some(level) = self.levels(&settings)?.get(levels as usize - 1) else {
+            return Ok(None);
+        };
+
+        let factor = 1 << levels;
+        let Ok(coarse_search) = level.clamp_region(shrink(search, factor)) else {
+            return Ok(None);
+        };
+        let mut coarse = template.image().pyr_down()?;
+        for _ in 1..levels {
+            coarse = coarse.pyr_down()?;
+        }
+        let coarse = Template::new(coarse);
+        let Some(result) = similarity_map(level, coarse_search, &coarse, pattern.method)? else {
+            return Ok(None);
+        };
+
+        let best = TemplateMatcher::best_peak(&result)?;
+        let mut peaks =
+            TemplateMatcher::peaks_above(&result, best.score - settings.score_tolerance)?;
+        peaks.sort_by(|a, b| b.score.total_cmp(&a.score));
+        peaks.truncate(settings.max_candidates);
+        debug!(
+            "Coarse pass at 1/{} found {} candidates for {}, best {:.4}",
+            factor,
+            peaks.len(),
+            pattern.image.path(),
+            best.score
+        );
+
+        // The coarse position is off by up to a coarse pixel either way
+        let margin = 2 * factor;
+        let mut refined: Option<Match> = None;
+        for peak in peaks {
+            let around = Region::new(
+                (coarse_search.x + peak.x) * factor - margin,
+                (coarse_search.y + peak.y) * factor - margin,
+                tw + 2 * margin,
+                th + 2 * margin,
+            );
+            let Some(area) = around.intersection(&search) else {
+                continue;
+            };
+            let Some(found) = self.full_match(area, template, pattern, scale)? else {
+                continue;
+            };
+            let is_better = match &refined {
+                Some(r) => found.score > r.score,
+                None => true,
+            };
+            if is_better {
+                refined = Some(found);
+            }
+        }
+        Ok(refined)
+    }
+
+    /// Get the halved copies of the searched image, building them once
+    fn levels(&self, settings: &PyramidSearch) -> Result<&[MatWrapper]> {
+        if let Some(levels) = self.levels.get() {
+            return Ok(levels.as_slice());
+        }
+        let levels = self.image.pyramid(settings.max_levels as usize, 1)?;
+        Ok(self.levels.get_or_init(|| levels).as_slice())
+    }
+
+    /// Clip a search region to the bounds of the searched image
+    fn clip_region(&self, region: Region) -> Result<Region> {
+        self.image.clamp_region(region)
+    }
+}
+
+/// Compute the score map of `template` over the clipped `search` area of
+/// `image`
+///
+/// Returns `None` when the template does not fit into the search area.
+fn similarity_map(
+    image: &MatWrapper,
+    search: Region,
+    template: &Template,
+    method: MatchMethod,
+) -> Result<Option<Mat>> {
+    let (tw, th) = template.size()?;
+    if tw > search.w || th > search.h {
+        debug!(
+            "Template {}x{} larger than search region {:?}",
+            tw, th, search
+        );
+        return Ok(None);
+    }
+
+    let haystack = image.view(search)?;
+    let result = if haystack.channels() == 3 {
+        score_map(haystack.as_mat(), template, method)?
+    } else {
+        score_map(haystack.to_bgr()?.as_mat(), template, method)?
+    };
+    Ok(Some(result))
+}
+
+/// Score a BGR haystack against `template` with `method`, honouring its mask
+fn score_map(
+    haystack: &impl ToInputArray,
+    template: &Template,
+    method: MatchMethod,
+) -> Result<Mat> {
+    let image = to_bgr(template.image().as_mat())?;
+    TemplateMatcher::score_map(
+        haystack,
+        &image,
+        template.mask().map(MatWrapper::as_mat),
+        method,
+    )
+}
+
+/// Run `f` on the template resized to each of the pattern's search scales
+///
+/// The template is passed through without copying at scale 1.0.
+fn for_each_scale(
+    template: &Template,
+    pattern: &Pattern,
+    mut f: impl FnMut(f32, &Template) -> Result<()>,
+) -> Result<()> {
+    for scale in pattern.search_scales() {
+        if (scale - 1.0).abs() < 1e-4 {
+            f(1.0, template)?;
+        } else {
+            let scaled = template.scaled(scale as f64)?;
+            f(scale, &scaled)?;
+        }
+    }
+    Ok(())
+}
+
+/// Build a match from a peak found inside the `search` area
+fn to_match(
+    search: Region,
+    peak: MatchPeak,
+    tw: i32,
+    th: i32,
+    scale: f32,
+    pattern: &Pattern,
+) -> Match {
+    let region = Region::new(search.x + peak.x, search.y + peak.y, tw, th);
+    Match::new(region, peak.score as f32)
+        .with_offset(pattern.target_offset)
+        .with_scale(scale)
+}
+
+/// Map a region onto an image `factor` times smaller, covering every
+/// pixel it touches
+fn shrink(region: Region, factor: i32) -> Region {
+    let x = region.x.div_euclid(factor);
+    let y = region.y.div_euclid(factor);
+    let right = (region.x + region.w + factor - 1).div_euclid(factor);
+    let bottom = (region.y + region.h + factor - 1).div_euclid(factor);
+    Region::new(x, y, right - x, bottom - y)
+}
+
+/// Greedy non-maximum suppression
+///
+/// Candidates are visited best first; a candidate is dropped when it
+/// overlaps an already kept match by more than `max_overlap`.
+fn suppress_overlaps(mut candidates: Vec<Match>, max_overlap: f64) -> Vec<Match> {
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    let mut kept: Vec<Match> = Vec::new();
+    for candidate in candidates {
+        let suppressed = kept.iter().any(|k| {
+            k.region.overlaps(&candidate.region)
+                && overlap_ratio(&k.region, &candidate.region) > max_overlap
+        });
+        if !suppressed {
+            kept.push(candidate);
+        }
+    }
+    kept
+}
+
+/// Intersection area relative to the smaller of the two regions
+fn overlap_ratio(a: &Region, b: &Region) -> f64 {
+    let smaller = a.area().min(b.area());
+    match a.intersection(b) {
+        Some(common) if smaller > 0 => common.area() as f64 / smaller as f64,
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resize::{resize_by_factor, Interpolation};
+    use crate::test_support::{noise_mat, png_fixture as fixture};
+    use opencv::core::{AlgorithmHint, Rect};
+    use opencv::core::{Vec3b, Vec4b};
+    use opencv::imgproc::{cvt_color, COLOR_BGR2BGRA};
+    use sikulix_core::{Error, Image, Location, MatchOrder, Offset};
+    use tempfile::TempDir;
+
+    fn pattern_for(path: &std::path::Path) -> Pattern {
+        Pattern::new(Image::from_path(path.to_str().unwrap()))
+    }
+
+    #[test]
+    fn test_find_exact_crop() {
+        let dir = TempDir::new().unwrap();
+        let screen = noise_mat(200, 150, 42);
+        let crop = screen
+            .roi(Rect::new(120, 80, 24, 16))
+            .unwrap()
+            .try_clone()
+            .unwrap();
+
+        let (_, haystack) = fixture(&dir, "screen.png", &screen);
+        let (needle_path, _) = fixture(&dir, "needle.png", &crop);
+
+        let finder = Finder::new(haystack);
+        let found = finder
+            .find(Region::new(0, 0, 200, 150), &pattern_for(&needle_path))
+            .unwrap()
+            .expect("pattern should be found");
+
+        assert_eq!(found.region, Region::new(120, 80, 24, 16));
+        assert!(found.score > 0.99);
+    }
+
+    #[test]
+    fn test_find_in_sub_region_reports_image_coordinates() {
+        let dir = TempDir::new().unwrap();
+        let screen = noise_mat(160, 120, 3);
+        let crop = screen
+            .roi(Rect::new(90, 70, 20, 20))
+            .unwrap()
+            .try_clone()
+            .unwrap();
+
+        let (_, haystack) = fixture(&dir, "screen.png", &screen);
+        let (needle_path, _) = fixture(&dir, "needle.png", &crop);
+
+        let finder = Finder::new(haystack);
+        let found = finder
+            .find(Region::new(80, 60, 50, 50), &pattern_for(&needle_path))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(found.top_left().x, 90);
+        assert_eq!(found.top_left().y, 70);
+    }
+
+    #[test]
+    fn test_find_carries_target_offset() {
+        let dir = TempDir::new().unwrap();
+        let screen = noise_mat(100, 100, 9);
+        let crop = screen
+            .roi(Rect::new(10, 20, 30, 30))
+            .unwrap()
+            .try_clone()
+            .unwrap();
+
+        let (_, haystack) = fixture(&dir, "screen.png", &screen);
+        let (needle_path, _) = fixture(&dir, "needle.png", &crop);
+
+        let pattern = pattern_for(&needle_path).target_offset(Offset::new(5, -5));
+        let found = Finder::new(haystack)
+            .find(Region::new(0, 0, 100, 100), &pattern)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(found.target_offset, Offset::new(5, -5));
+        assert_eq!(found.target().x, 25 + 5);
+        assert_eq!(found.target().y, 35 - 5);
+    }
+
+    #[test]
+    fn test_find_respects_similarity() {
+        let dir = TempDir::new().unwrap();
+        let screen = noise_mat(100, 100, 11);
+        let unrelated = noise_mat(20, 20, 12);
+
+        let (_, haystack) = fixture(&dir, "screen.png", &screen);
+        let (needle_path, _) = fixture(&dir, "needle.png", &unrelated);
+
+        let finder = Finder::new(haystack);
+        let strict = pattern_for(&needle_path).similar(0.9);
+        assert!(finder
+            .find(Region::new(0, 0, 100, 100), &strict)
+            .unwrap()
+            .is_none());
+
+        let anything = pattern_for(&needle_path).similar(0.0);
+        assert!(finder
+            .find(Region::new(0, 0, 100, 100), &anything)
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_find_template_larger_than_region() {
+        let dir = TempDir::new().unwrap();
+        let screen = noise_mat(50, 50, 5);
+        let (_, haystack) = fixture(&dir, "screen.png", &screen);
+        let template = Template::new(MatWrapper::new(noise_mat(30, 30, 6)));
+
+        let finder = Finder::new(haystack);
+        let result = finder
+            .find_template(
+                Region::new(0, 0, 20, 20),
+                &template,
+                &Pattern::new(Image::from_path("t.png")),
+            )
+            .unwrap();
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn test_find_region_outside_image() {
+        let finder = Finder::new(MatWrapper::new(noise_mat(50, 50, 1)));
+        let template = Template::new(MatWrapper::new(noise_mat(5, 5, 2)));
+
+        let result = finder.find_template(
+            Region::new(100, 100, 10, 10),
+            &template,
+            &Pattern::new(Image::from_path("t.png")),
+        );
+        assert!(matches!(result.unwrap_err(), Error::InvalidRegion(_)));
+    }
+
+    /// Screen with smooth gradients, which survive downsampling
+    fn smooth_screen(width: i32, height: i32, seed: u32) -> Mat {
+        let coarse = MatWrapper::new(noise_mat(width / 8, height / 8, seed));
+        coarse
+            .resize(width, height, Interpolation::Cubic)
+            .unwrap()
+            .into_mat()
+    }
+
+    #[test]
+    fn test_pyramid_levels_for_template_size() {
+        let settings = PyramidSearch::default();
+        assert_eq!(settings.levels_for(24, 16), 0);
+        assert_eq!(settings.levels_for(30, 30), 1);
+        assert_eq!(settings.levels_for(96, 64), 2);
+        assert_eq!(settings.levels_for(200, 150), 3);
+
+        let shallow = PyramidSearch {
+            max_levels: 1,
+            ..PyramidSearch::default()
+        };
+        assert_eq!(shallow.levels_for(200, 150), 1);
+    }
+
+    #[test]
+    fn test_pyramid_search_matches_full_search() {
+        let dir = TempDir::new().unwrap();
+        let screen = smooth_screen(512, 384, 31);
+        let crop = screen
+            .roi(Rect::new(203, 141, 96, 64))
+            .unwrap()
+            .try_clone()
+            .unwrap();
+
+        let (_, haystack) = fixture(&dir, "screen.png", &screen);
+        let (needle_path, _) = fixture(&dir, "needle.png", &crop);
+        let pattern = pattern_for(&needle_path);
+        let region = Region::new(0, 0, 512, 384);
+
+        let full = Finder::new(haystack.clone_mat().unwrap())
+            .pyramid_search(None)
+            .find(region, &pattern)
+            .unwrap()
+            .unwrap();
+        let finder = Finder::new(haystack);
+        let coarse = finder.find(region, &pattern).unwrap().unwrap();
+
+        assert_eq!(coarse.region, Region::new(203, 141, 96, 64));
+        assert_eq!(coarse.region, full.region);
+        assert!((coarse.score - full.score).abs() < 1e-4);
+
+        // Sub-regions map onto the coarse levels too
+        let inside = finder
+            .find(Region::new(150, 101, 201, 150), &pattern)
+            .unwrap()
+            .unwrap();
+        assert_eq!(inside.region, coarse.region);
+    }
+
+    #[test]
+    fn test_pyramid_search_finds_noise_at_odd_offset() {
+        // Shrinking high-frequency noise loses most of its score
+        let dir = TempDir::new().unwrap();
+        let screen = noise_mat(512, 384, 33);
+        let crop = screen
+            .roi(Rect::new(203, 141, 96, 64))
+            .unwrap()
+            .try_clone()
+            .unwrap();
+
+        let (_, haystack) = fixture(&dir, "screen.png", &screen);
+        let (needle_path, _) = fixture(&dir, "needle.png", &crop);
+
+        let finder = Finder::new(haystack).pyramid_search(Some(PyramidSearch::default()));
+        let found = finder
+            .find(
+                Region::new(0, 0, 512, 384),
+                &pattern_for(&needle_path).similar(0.95),
+            )
+            .unwrap()
+            .expect("pattern should be found");
+        assert_eq!(found.region, Region::new(203, 141, 96, 64));
+        assert!(found.score > 0.99);
+    }
+
+    #[test]
+    fn test_pyramid_search_not_found() {
+        let dir = TempDir::new().unwrap();
+        let (_, haystack) = fixture(&dir, "screen.png", &smooth_screen(512, 384, 31));
+        let (needle_path, _) = fixture(&dir, "needle.png", &smooth_screen(96, 64, 32));
+
+        let result = Finder::new(haystack)
+            .find(
+                Region::new(0, 0, 512, 384),
+                &pattern_for(&needle_path).similar(0.99),
+            )
+            .unwrap();
+        assert!(result.is_none());
+    }
+
+    /// Noise screen with copies of one icon pasted at `positions`
+    fn screen_with_icons(positions: &[(i32, i32)]) -> (Mat, Mat) {
+        let mut screen = noise_mat(240, 160, 21);
+        let icon = noise_mat(16, 16, 22);
+        for &(x, y) in positions {
+            let mut target = screen.roi_mut(Rect::new(x, y, 16, 16)).unwrap();
+            icon.copy_to(&mut target).unwrap();
+        }
+        (screen, icon)
+    }
+
+    #[test]
+    fn test_find_all_reports_every_occurrence() {
+        let dir = TempDir::new().unwrap();
+        let positions = [(200, 10), (20, 100), (120, 60)];
+        let (screen, icon) = screen_with_icons(&positions);
+
+        let (_, haystack) = fixture(&dir, "screen.png", &screen);
+        let (icon_path, _) = fixture(&dir, "icon.png", &icon);
+
+        let matches = Finder::new(haystack)
+            .find_all(
+                Region::new(0, 0, 240, 160),
+                &pattern_for(&icon_path).similar(0.9),
+            )
+            .unwrap();
+
+        assert_eq!(matches.len(), 3);
+        let mut found: Vec<_> = matches.iter().map(|m| (m.region.x, m.region.y)).collect();
+        found.sort();
+        assert_eq!(found, vec![(20, 100), (120, 60), (200, 10)]);
+        assert!(matches.iter().all(|m| m.score > 0.99));
+    }
+
+    #[test]
+    fn test_find_all_sorted_orders() {
+        let dir = TempDir::new().unwrap();
+        let (screen, icon) = screen_with_icons(&[(200, 10), (20, 100), (120, 60)]);
+
+        let (_, haystack) = fixture(&dir, "screen.png", &screen);
+        let (icon_path, _) = fixture(&dir, "icon.png", &icon);
+
+        let matches = Finder::new(haystack)
+            .find_all(
+                Region::new(0, 0, 240, 160),
+                &pattern_for(&icon_path).similar(0.9),
+            )
+            .unwrap();
+
+        let reading: Vec<_> = matches
+            .clone()
+            .sorted(MatchOrder::ReadingOrder)
+            .iter()
+            .map(|m| m.region.x)
+            .collect();
+        assert_eq!(reading, vec![200, 120, 20]);
+
+        let nearest = matches.sorted(MatchOrder::DistanceFrom(Location::new(0, 160)));
+        assert_eq!(nearest.get(0).unwrap().region.x, 20);
+    }
+
+    #[test]
+    fn test_find_all_none_found() {
+        let dir = TempDir::new().unwrap();
+        let (screen, _) = screen_with_icons(&[]);
+        let (_, haystack) = fixture(&dir, "screen.png", &screen);
+        let (other_path, _) = fixture(&dir, "other.png", &noise_mat(16, 16, 99));
+
+        let matches = Finder::new(haystack)
+            .find_all(
+                Region::new(0, 0, 240, 160),
+                &pattern_for(&other_path).similar(0.9),
+            )
+            .unwrap();
+        assert!(matches.is_empty());
+    }
+
+    #[test]
+    fn test_suppress_overlaps_keeps_best() {
+        let candidates = vec![
+            Match::new(Region::new(0, 0, 10, 10), 0.80),
+            Match::new(Region::new(2, 1, 10, 10), 0.95),
+            Match::new(Region::new(30, 0, 10, 10), 0.85),
+            Match::new(Region::new(8, 8, 10, 10), 0.90),
+        ];
+
+        let kept = suppress_overlaps(candidates.clone(), 0.0);
+        let scores: Vec<f32> = kept.iter().map(|m| m.score).collect();
+        assert_eq!(scores, vec![0.95, 0.85]);
+
+        // (8, 8) only shares a 4x3 corner with (2, 1): 12% of its area
+        let kept = suppress_overlaps(candidates, 0.5);
+        let scores: Vec<f32> = kept.iter().map(|m| m.score).collect();
+        assert_eq!(scores, vec![0.95, 0.90, 0.85]);
+    }
+
+    #[test]
+    fn test_overlap_ratio() {
+        let a = Region::new(0, 0, 10, 10);
+        assert_eq!(overlap_ratio(&a, &Region::new(5, 0, 10, 10)), 0.5);
+        assert_eq!(overlap_ratio(&a, &Region::new(0, 0, 4, 4)), 1.0);
+        assert_eq!(overlap_ratio(&a, &Region::new(20, 20, 5, 5)), 0.0);
+    }
+
+    /// Noise screen with the icon pasted at (100, 50), enlarged by `scale`
+    fn screen_with_scaled_icon(scale: f64) -> (Mat, Mat) {
+        let mut screen = noise_mat(240, 160, 31);
+        let icon = noise_mat(20, 20, 32);
+        let scaled = resize_by_factor(&MatWrapper::new(icon.try_clone().unwrap()), scale)
+            .unwrap()
+            .into_mat();
+        let (w, h) = (scaled.cols(), scaled.rows());
+        let mut target = screen.roi_mut(Rect::new(100, 50, w, h)).unwrap();
+        scaled.copy_to(&mut target).unwrap();
+        (screen, icon)
+    }
+
+    #[test]
+    fn test_find_multi_scale_reports_winning_scale() {
+        let dir = TempDir::new().unwrap();
+        let (screen, icon) = screen_with_scaled_icon(1.25);
+        let (_, haystack) = fixture(&dir, "screen.png", &screen);
+        let (icon_path, _) = fixture(&dir, "icon.png", &icon);
+        let finder = Finder::new(haystack);
+
+        let single = pattern_for(&icon_path).similar(0.9);
+        assert!(finder
+            .find(Region::new(0, 0, 240, 160), &single)
+            .unwrap()
+            .is_none());
+
+        let multi = single.scale_range(1.0, 1.5, 0.25);
+        let found = finder
+            .find(Region::new(0, 0, 240, 160), &multi)
+            .unwrap()
+            .expect("scaled icon should be found");
+
+        assert_eq!(found.scale, 1.25);
+        assert_eq!(found.region, Region::new(100, 50, 25, 25));
+        assert!(found.score > 0.95);
+    }
+
+    #[test]
+    fn test_find_all_multi_scale() {
+        let dir = TempDir::new().unwrap();
+        let (screen, icon) = screen_with_scaled_icon(1.5);
+        let (_, haystack) = fixture(&dir, "screen.png", &screen);
+        let (icon_path, _) = fixture(&dir, "icon.png", &icon);
+
+        let pattern = pattern_for(&icon_path)
+            .similar(0.9)
+            .scale_factors(vec![1.0, 1.25, 1.5]);
+        let matches = Finder::new(haystack)
+            .find_all(Region::new(0, 0, 240, 160), &pattern)
+            .unwrap();
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches.get(0).unwrap().scale, 1.5);
+    }
+
+    /// Icon whose corners are transparent, and a screen showing only its opaque
+    /// centre over a different background
+    fn screen_with_transparent_icon() -> (Mat, Mat) {
+        let mut screen = noise_mat(200, 120, 41);
+        let colours = noise_mat(24, 24, 42);
+        let mut icon = Mat::default();
+        cvt_color(
+            &colours,
+            &mut icon,
+            COLOR_BGR2BGRA,
+            0,
+            AlgorithmHint::ALGO_HINT_DEFAULT,
+        )
+        .unwrap();
+
+        for y in 0..24 {
+            for x in 0..24 {
+                let (dx, dy) = (x as f64 - 11.5, y as f64 - 11.5);
+                if dx * dx + dy * dy > 100.0 {
+                    icon.at_2d_mut::<Vec4b>(y, x).unwrap()[3] = 0;
+                } else {
+                    *screen.at_2d_mut::<Vec3b>(50 + y, 80 + x).unwrap() =
+                        *colours.at_2d::<Vec3b>(y, x).unwrap();
+                }
+            }
+        }
+        (screen, icon)
+    }
+
+    #[test]
+    fn test_find_with_alpha_mask() {
+        let dir = TempDir::new().unwrap();
+        let (screen, icon) = screen_with_transparent_icon();
+        let (_, haystack) = fixture(&dir, "screen.png", &screen);
+        let (icon_path, _) = fixture(&dir, "icon.png", &icon);
+        let finder = Finder::new(haystack);
+
+        let unmasked = pattern_for(&icon_path).similar(0.9);
+        assert!(finder
+            .find(Region::new(0, 0, 200, 120), &unmasked)
+            .unwrap()
+            .is_none());
+
+        let found = finder
+            .find(Region::new(0, 0, 200, 120), &unmasked.mask_from_alpha())
+            .unwrap()
+            .expect("opaque part of the icon should be found");
+        assert_eq!(found.region, Region::new(80, 50, 24, 24));
+        assert!(found.score > 0.99);
+    }
+
+    #[test]
+    fn test_find_all_with_mask_image() {
+        let dir = TempDir::new().unwrap();
+        let (screen, icon) = screen_with_transparent_icon();
+        let mut alpha = Mat::default();
+        opencv::core::extract_channel(&icon, &mut alpha, 3).unwrap();
+
+        let (_, haystack) = fixture(&dir, "screen.png", &screen);
+        let (icon_path, _) = fixture(&dir, "icon.png", &icon);
+        let (mask_path, _) = fixture(&dir, "mask.png", &alpha);
+
+        let pattern = pattern_for(&icon_path)
+            .similar(0.9)
+            .mask(Image::from_path(mask_path.to_str().unwrap()));
+        let matches = Finder::new(haystack)
+            .find_all(Region::new(0, 0, 200, 120), &pattern)
+            .unwrap();
+
+        assert_eq!(matches.len(), 1);
+        let found = matches.get(0).unwrap();
+        assert_eq!((found.region.x, found.region.y), (80, 50));
+    }
+
+    #[test]
+    fn test_find_with_match_method() {
+        let dir = TempDir::new().unwrap();
+        let screen = noise_mat(120, 80, 51);
+        let crop = screen
+            .roi(Rect::new(70, 30, 16, 12))
+            .unwrap()
+            .try_clone()
+            .unwrap();
+
+        let (_, haystack) = fixture(&dir, "screen.png", &screen);
+        let (needle_path, _) = fixture(&dir, "needle.png", &crop);
+
+        let pattern = pattern_for(&needle_path).method(MatchMethod::SqDiff);
+        let found = Finder::new(haystack)
+            .find(Region::new(0, 0, 120, 80), &pattern)
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.region, Region::new(70, 30, 16, 12));
+        assert!(found.score > 0.999);
     }
 
-    pub fn find(&self, _region: Region, _pattern: Pattern) -> Result<Option<Match>> {
-        Err(Error::Other(anyhow::anyhow!("Not implemented yet")))
+    #[test]
+    fn test_find_missing_pattern_file() {
+        let finder = Finder::new(MatWrapper::new(noise_mat(50, 50, 1)));
+        let result = finder.find(
+            Region::new(0, 0, 50, 50),
+            &Pattern::new(Image::from_path("does_not_exist.png")),
+        );
+        assert!(matches!(result.unwrap_err(), Error::ImageNotFound(_)));
     }
 }
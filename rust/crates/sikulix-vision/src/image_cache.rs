@@ -0,0 +1,388 @@
+//! Cache of decoded images, like Java's `Image` cache
+//!
+//! Patterns are loaded again for every search. [`ImageCache`] keeps the
+//! decoded pixels, keyed by canonical path, modification time and length,
+//! so only the first search pays for reading and decoding the file. Editing
+//! the file changes its modification time or length, which makes the next
+//! load decode it again. A replacement of the same length that keeps the
+//! modification time needs [`ImageCache::invalidate`].
+
+use crate::image_loader::ImageLoader;
+use crate::mat_wrapper::MatWrapper;
+use crate::resize::resize_by_factor;
+use opencv::prelude::*;
+use sikulix_core::{Error, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
+use std::time::SystemTime;
+use tracing::trace;
+
+/// Default memory cap of the cache, Java's `Settings.MaxCachedImages` of
+/// 64 MB
+pub const DEFAULT_MAX_BYTES: usize = 64 * 1024 * 1024;
+
+/// The cache shared by the whole process
+static GLOBAL: OnceLock<ImageCache> = OnceLock::new();
+
+/// Hit and miss counts of an `ImageCache`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    /// Loads answered from the cache
+    pub hits: u64,
+
+    /// Loads that decoded the file
+    pub misses: u64,
+
+    /// Images dropped to stay under the memory cap
+    pub evictions: u64,
+
+    /// Images currently cached
+    pub entries: usize,
+
+    /// Pixel bytes currently cached
+    pub bytes: usize,
+}
+
+/// Decoded form of a file kept in the cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Variant {
+    /// BGR, as `ImageLoader::load_from_file(path, true)`
+    Color,
+    /// With alpha if present, as `ImageLoader::load_from_file(path, false)`
+    Unchanged,
+    /// Single channel, as `ImageLoader::load_as_grayscale`
+    Grayscale,
+    /// BGR resized by the factor with these `f64` bits
+    Scaled(u64),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Key {
+    path: PathBuf,
+    modified: SystemTime,
+    len: u64,
+    variant: Variant,
+}
+
+#[derive(Debug)]
+struct Entry {
+    image: Arc<MatWrapper>,
+    bytes: usize,
+    /// Value of `State::tick` when the entry was last used
+    used: u64,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    entries: HashMap<Key, Entry>,
+    tick: u64,
+    stats: CacheStats,
+}
+
+/// A least-recently-used cache of decoded images with a memory cap
+///
+/// Images are shared as `Arc<MatWrapper>`; use `MatWrapper::clone_mat` to
+/// get a copy that may be modified.
+#[derive(Debug)]
+pub struct ImageCache {
+    state: Mutex<State>,
+    max_bytes: AtomicUsize,
+}
+
+impl ImageCache {
+    /// Create a cache holding at most `max_bytes` of pixel data
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            state: Mutex::new(State::default()),
+            max_bytes: AtomicUsize::new(max_bytes),
+        }
+    }
+
+    /// Get the cache shared by the whole process, used by `Template::load`
+    pub fn global() -> &'static ImageCache {
+        GLOBAL.get_or_init(|| ImageCache::new(DEFAULT_MAX_BYTES))
+    }
+
+    /// Load an image like `ImageLoader::load_from_file`
+    pub fn load(&self, path: impl AsRef<Path>, color: bool) -> Result<Arc<MatWrapper>> {
+        let variant = if color {
+            Variant::Color
+        } else {
+            Variant::Unchanged
+        };
+        self.get_or_decode(path.as_ref(), variant, |path| {
+            ImageLoader::load_from_file(path, color)
+        })
+    }
+
+    /// Load an image like `ImageLoader::load_as_grayscale`
+    pub fn load_grayscale(&self, path: impl AsRef<Path>) -> Result<Arc<MatWrapper>> {
+        self.get_or_decode(path.as_ref(), Variant::Grayscale, |path| {
+            ImageLoader::load_as_grayscale(path)
+        })
+    }
+
+    /// Load a BGR image resized by `factor`, see `resize_by_factor`
+    pub fn load_scaled(&self, path: impl AsRef<Path>, factor: f64) -> Result<Arc<MatWrapper>> {
+        let path = path.as_ref();
+        self.get_or_decode(path, Variant::Scaled(factor.to_bits()), |_| {
+            resize_by_factor(&self.load(path, true)?, factor)
+        })
+    }
+
+    /// Drop every cached form of the file at `path`
+    pub fn invalidate(&self, path: impl AsRef<Path>) {
+        let path = fs::canonicalize(path.as_ref()).unwrap_or_else(|_| path.as_ref().into());
+        let mut state = self.state();
+        state.entries.retain(|key, _| key.path != path);
+        state.update_size();
+    }
+
+    /// Drop all cached images
+    pub fn clear(&self) {
+        let mut state = self.state();
+        state.entries.clear();
+        state.update_size();
+    }
+
+    /// Get the hit and miss counts and the current size
+    pub fn stats(&self) -> CacheStats {
+        self.state().stats
+    }
+
+    /// Get the memory cap in bytes of pixel data
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Change the memory cap, evicting images that no longer fit
+    ///
+    /// A cap of zero disables caching.
+    pub fn set_max_bytes(&self, max_bytes: usize) {
+        self.max_bytes.store(max_bytes, Ordering::Relaxed);
+        self.state().evict(max_bytes);
+    }
+
+    fn get_or_decode(
+        &self,
+        path: &Path,
+        variant: Variant,
+        decode: impl FnOnce(&Path) -> Result<MatWrapper>,
+    ) -> Result<Arc<MatWrapper>> {
+        let key = match key(path, variant) {
+            Some(key) => key,
+            // Let the loader report the missing or unreadable file
+            None => return decode(path).map(Arc::new),
+        };
+
+        {
+            let mut state = self.state();
+            state.tick += 1;
+            let tick = state.tick;
+            if let Some(entry) = state.entries.get_mut(&key) {
+                entry.used = tick;
+                let image = entry.image.clone();
+                state.stats.hits += 1;
+                return Ok(image);
+            }
+            state.stats.misses += 1;
+        }
+
+        // Decode without the lock so other images stay available meanwhile
+        let image = Arc::new(decode(&key.path)?);
+        let bytes = image_bytes(&image)?;
+        let max_bytes = self.max_bytes();
+        if bytes > max_bytes {
+            trace!("Not caching {}: {} bytes", key.path.display(), bytes);
+            return Ok(image);
+        }
+
+        let mut state = self.state();
+        // An older version of the file is not needed anymore
+        state
+            .entries
+            .retain(|k, _| k.path != key.path || (k.modified, k.len) == (key.modified, key.len));
+        state.tick += 1;
+        let used = state.tick;
+        state.entries.insert(
+            key,
+            Entry {
+                image: image.clone(),
+                bytes,
+                used,
+            },
+        );
+        state.evict(max_bytes);
+        Ok(image)
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        // Every update leaves the entries consistent, even if interrupted
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl State {
+    /// Drop least recently used entries until at most `max_bytes` are used
+    fn evict(&mut self, max_bytes: usize) {
+        self.update_size();
+        while self.stats.bytes > max_bytes {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            self.entries.remove(&oldest);
+            self.stats.evictions += 1;
+            self.update_size();
+        }
+    }
+
+    fn update_size(&mut self) {
+        self.stats.entries = self.entries.len();
+        self.stats.bytes = self.entries.values().map(|entry| entry.bytes).sum();
+    }
+}
+
+/// Cache key of `path`, or `None` if the file cannot be inspected
+fn key(path: &Path, variant: Variant) -> Option<Key> {
+    let path = fs::canonicalize(path).ok()?;
+    let metadata = fs::metadata(&path).ok()?;
+    Some(Key {
+        path,
+        modified: metadata.modified().ok()?,
+        len: metadata.len(),
+        variant,
+    })
+}
+
+fn image_bytes(image: &MatWrapper) -> Result<usize> {
+    let mat = image.as_mat();
+    let elem_size = mat
+        .elem_size()
+        .map_err(|e| Error::Platform(format!("Failed to get element size: {}", e)))?;
+    Ok(mat.total() * elem_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{noise_mat, png_fixture};
+    use std::fs::File;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_hits_and_variants() {
+        let dir = TempDir::new().unwrap();
+        let (path, _) = png_fixture(&dir, "button.png", &noise_mat(20, 10, 1));
+        let cache = ImageCache::new(DEFAULT_MAX_BYTES);
+
+        let first = cache.load(&path, true).unwrap();
+        let second = cache
+            .load(dir.path().join(".").join("button.png"), true)
+            .unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        let gray = cache.load_grayscale(&path).unwrap();
+        assert_eq!(gray.channels().unwrap(), 1);
+        let scaled = cache.load_scaled(&path, 0.5).unwrap();
+        assert_eq!(scaled.size().unwrap(), (10, 5));
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (2, 3));
+        assert_eq!(stats.entries, 3);
+        assert_eq!(stats.bytes, 20 * 10 * 3 + 20 * 10 + 10 * 5 * 3);
+    }
+
+    #[test]
+    fn test_modified_file_is_reloaded() {
+        let dir = TempDir::new().unwrap();
+        let (path, _) = png_fixture(&dir, "button.png", &noise_mat(20, 10, 1));
+        let cache = ImageCache::new(DEFAULT_MAX_BYTES);
+        let before = cache.load(&path, true).unwrap();
+
+        png_fixture(&dir, "button.png", &noise_mat(30, 10, 2));
+        let later = SystemTime::now() + Duration::from_secs(10);
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+
+        let after = cache.load(&path, true).unwrap();
+        assert!(!Arc::ptr_eq(&before, &after));
+        assert_eq!(after.size().unwrap(), (30, 10));
+        assert_eq!(cache.stats().entries, 1);
+
+        cache.invalidate(&path);
+        assert_eq!(cache.stats().entries, 0);
+        cache.load(&path, true).unwrap();
+        assert_eq!(cache.stats().misses, 3);
+    }
+
+    #[test]
+    fn test_replaced_file_with_same_mtime_is_reloaded() {
+        let dir = TempDir::new().unwrap();
+        let (path, _) = png_fixture(&dir, "button.png", &noise_mat(20, 10, 1));
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        let cache = ImageCache::new(DEFAULT_MAX_BYTES);
+        let before = cache.load(&path, true).unwrap();
+
+        // Like `cp -p`: new content, old modification time
+        png_fixture(&dir, "button.png", &noise_mat(30, 10, 2));
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+
+        let after = cache.load(&path, true).unwrap();
+        assert!(!Arc::ptr_eq(&before, &after));
+        assert_eq!(after.size().unwrap(), (30, 10));
+        assert_eq!(cache.stats().entries, 1);
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let dir = TempDir::new().unwrap();
+        let (a, _) = png_fixture(&dir, "a.png", &noise_mat(10, 10, 1));
+        let (b, _) = png_fixture(&dir, "b.png", &noise_mat(10, 10, 2));
+        let (c, _) = png_fixture(&dir, "c.png", &noise_mat(10, 10, 3));
+        let cache = ImageCache::new(2 * 300);
+
+        cache.load(&a, true).unwrap();
+        cache.load(&b, true).unwrap();
+        cache.load(&a, true).unwrap();
+        cache.load(&c, true).unwrap();
+
+        // b was used least recently
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.evictions), (2, 1));
+        cache.load(&a, true).unwrap();
+        assert_eq!(cache.stats().hits, 2);
+
+        cache.set_max_bytes(0);
+        assert_eq!(cache.stats().entries, 0);
+        cache.load(&a, true).unwrap();
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn test_missing_file_is_reported_by_loader() {
+        let cache = ImageCache::new(DEFAULT_MAX_BYTES);
+        assert!(matches!(
+            cache.load("/nonexistent/button.png", true),
+            Err(Error::ImageNotFound(_))
+        ));
+        assert_eq!(cache.stats(), CacheStats::default());
+    }
+}
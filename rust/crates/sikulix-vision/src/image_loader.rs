@@ -4,7 +4,8 @@ use crate::mat_wrapper::MatWrapper;
 use opencv::imgcodecs::{imread, imdecode, IMREAD_COLOR, IMREAD_UNCHANGED};
 use opencv::core::{Vector, Mat, AlgorithmHint};
 use opencv::prelude::*;
-use sikulix_core::{Error, Result};
+use sikulix_core::image_header::probe_dimensions;
+use sikulix_core::{Error, Image, Result};
 use std::path::Path;
 use tracing::{debug, trace};
 
@@ -135,13 +136,33 @@ impl ImageLoader {
 
     /// Get image dimensions without fully loading it (if possible)
     ///
-    /// For now, this loads the full image. Future optimization could use
-    /// format-specific header parsing.
+    /// PNG, JPEG, BMP, WebP and TIFF dimensions are read from the file
+    /// header. Other formats are decoded.
     pub fn get_dimensions<P: AsRef<Path>>(path: P) -> Result<(u32, u32)> {
+        let path = path.as_ref();
+        if path.is_file() {
+            if let Some(dimensions) = probe_dimensions(path)? {
+                return Ok(dimensions);
+            }
+            trace!("No known header in {}, decoding it", path.display());
+        }
         let mat = Self::load_from_file(path, false)?;
         let (w, h) = mat.size()?;
         Ok((w as u32, h as u32))
     }
+
+    /// Fill in the dimensions of `image` if they are not known yet
+    ///
+    /// Like `Image::load_dimensions`, but decodes formats whose header is
+    /// not understood.
+    pub fn load_dimensions(image: &mut Image) -> Result<(u32, u32)> {
+        if !image.has_dimensions() {
+            let (width, height) = Self::get_dimensions(image.path_buf())?;
+            image.width = width;
+            image.height = height;
+        }
+        Ok(image.dimensions())
+    }
 }
 
 #[cfg(test)]
@@ -259,6 +280,32 @@ mod tests {
         assert_eq!(result.unwrap(), (123, 456));
     }
 
+    #[test]
+    fn test_get_dimensions_reads_headers_only() {
+        let temp_dir = TempDir::new().unwrap();
+        let mat = Mat::new_rows_cols_with_default(23, 37, CV_8UC3, (255, 128, 64, 0).into()).unwrap();
+
+        // Header parsing and the decoding fallback agree
+        for ext in ["jpg", "bmp", "ppm"] {
+            let mut buf = Vector::<u8>::new();
+            imencode(&format!(".{}", ext), &mat, &mut buf, &Vector::new()).unwrap();
+            let path = temp_dir.path().join(format!("sized.{}", ext));
+            fs::write(&path, buf.to_vec()).unwrap();
+            assert_eq!(ImageLoader::get_dimensions(&path).unwrap(), (37, 23), "{}", ext);
+        }
+
+        // A PNG cut after its header cannot be decoded, but has dimensions
+        let png = fs::read(create_test_image(&temp_dir, "cut.png", 37, 23)).unwrap();
+        let cut = temp_dir.path().join("cut.png");
+        fs::write(&cut, &png[..33]).unwrap();
+        assert!(ImageLoader::load_from_file(&cut, false).is_err());
+        assert_eq!(ImageLoader::get_dimensions(&cut).unwrap(), (37, 23));
+
+        let mut image = Image::from_path(cut.to_string_lossy());
+        assert_eq!(ImageLoader::load_dimensions(&mut image).unwrap(), (37, 23));
+        assert!(image.has_dimensions());
+    }
+
     #[test]
     fn test_load_color_vs_unchanged() {
         let temp_dir = TempDir::new().unwrap();
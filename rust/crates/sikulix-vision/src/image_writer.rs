@@ -0,0 +1,378 @@
+//! Image encoding to memory buffers and files, the counterpart of
+//! `ImageLoader`
+
+use crate::mat_wrapper::MatWrapper;
+use opencv::core::{AlgorithmHint, Mat, Vector};
+use opencv::imgcodecs::{
+    imencode, IMWRITE_JPEG_QUALITY, IMWRITE_PNG_COMPRESSION, IMWRITE_WEBP_QUALITY,
+};
+use opencv::imgproc::{cvt_color, COLOR_BGRA2BGR};
+use opencv::prelude::*;
+use sikulix_core::{Error, Result};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tracing::debug;
+
+/// Counts temporary files, so concurrent saves never share one
+static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// File formats `ImageWriter` can produce
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// Lossless, keeps alpha
+    Png,
+    /// Lossy, drops alpha
+    Jpeg,
+    /// Lossless unless a quality is set, keeps alpha
+    WebP,
+}
+
+impl ImageFormat {
+    /// Get the format for a file extension such as `png` or `.JPG`
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidParameter` for other extensions.
+    pub fn from_extension(ext: &str) -> Result<Self> {
+        match ext.trim_start_matches('.').to_ascii_lowercase().as_str() {
+            "png" => Ok(Self::Png),
+            "jpg" | "jpeg" => Ok(Self::Jpeg),
+            "webp" => Ok(Self::WebP),
+            other => Err(Error::InvalidParameter(format!(
+                "Unsupported image extension: {:?} (expected png, jpg, jpeg or webp)",
+                other
+            ))),
+        }
+    }
+
+    /// Get the format for the extension of `path`
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let ext = path.as_ref().extension().unwrap_or_default();
+        Self::from_extension(&ext.to_string_lossy())
+    }
+
+    /// Get the extension OpenCV encodes this format for
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => ".png",
+            Self::Jpeg => ".jpg",
+            Self::WebP => ".webp",
+        }
+    }
+
+    /// Check if the format stores an alpha channel
+    pub fn supports_alpha(self) -> bool {
+        !matches!(self, Self::Jpeg)
+    }
+}
+
+/// Encoder for PNG, JPEG and WebP images
+///
+/// Files are written atomically: the image goes to a temporary file next to
+/// the target, which is then renamed, so readers never see a partial image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageWriter {
+    jpeg_quality: u8,
+    png_compression: u8,
+    webp_quality: Option<u8>,
+}
+
+impl Default for ImageWriter {
+    fn default() -> Self {
+        Self {
+            jpeg_quality: 95,
+            png_compression: 3,
+            webp_quality: None,
+        }
+    }
+}
+
+impl ImageWriter {
+    /// Create a writer with the default quality and compression
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the JPEG quality, from 0 to 100 (default 95)
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidParameter` if `quality` is above 100.
+    pub fn with_jpeg_quality(mut self, quality: u8) -> Result<Self> {
+        if quality > 100 {
+            return Err(Error::InvalidParameter(format!(
+                "JPEG quality must be at most 100, got {}",
+                quality
+            )));
+        }
+        self.jpeg_quality = quality;
+        Ok(self)
+    }
+
+    /// Set the PNG compression level, from 0 (fastest) to 9 (smallest,
+    /// default 3)
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidParameter` if `level` is above 9.
+    pub fn with_png_compression(mut self, level: u8) -> Result<Self> {
+        if level > 9 {
+            return Err(Error::InvalidParameter(format!(
+                "PNG compression must be at most 9, got {}",
+                level
+            )));
+        }
+        self.png_compression = level;
+        Ok(self)
+    }
+
+    /// Encode WebP lossy with a quality from 1 to 100, instead of lossless
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidParameter` if `quality` is not between 1 and
+    /// 100.
+    pub fn with_webp_quality(mut self, quality: u8) -> Result<Self> {
+        if !(1..=100).contains(&quality) {
+            return Err(Error::InvalidParameter(format!(
+                "WebP quality must be between 1 and 100, got {}",
+                quality
+            )));
+        }
+        self.webp_quality = Some(quality);
+        Ok(self)
+    }
+
+    /// Get the JPEG quality
+    pub fn jpeg_quality(&self) -> u8 {
+        self.jpeg_quality
+    }
+
+    /// Get the PNG compression level
+    pub fn png_compression(&self) -> u8 {
+        self.png_compression
+    }
+
+    /// Get the WebP quality, or `None` for lossless
+    pub fn webp_quality(&self) -> Option<u8> {
+        self.webp_quality
+    }
+
+    /// Encode an image into a memory buffer
+    ///
+    /// Alpha is kept for PNG and WebP and dropped for JPEG.
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidParameter` for an empty image and
+    /// `Error::Platform` if OpenCV cannot encode it.
+    pub fn encode(&self, image: &MatWrapper, format: ImageFormat) -> Result<Vec<u8>> {
+        if image.is_empty() {
+            return Err(Error::InvalidParameter(
+                "Cannot encode an empty image".to_string(),
+            ));
+        }
+
+        let mut bgr = Mat::default();
+        let mat = if image.channels()? == 4 && !format.supports_alpha() {
+            cvt_color(
+                image.as_mat(),
+                &mut bgr,
+                COLOR_BGRA2BGR,
+                0,
+                AlgorithmHint::ALGO_HINT_DEFAULT,
+            )
+            .map_err(|e| Error::Platform(format!("Failed to drop alpha channel: {}", e)))?;
+            &bgr
+        } else {
+            image.as_mat()
+        };
+
+        let params = Vector::<i32>::from_slice(&self.params(format));
+        let mut buf = Vector::<u8>::new();
+        let encoded = imencode(format.extension(), mat, &mut buf, &params)
+            .map_err(|e| Error::Platform(format!("Failed to encode image: {}", e)))?;
+        if !encoded {
+            return Err(Error::Platform(format!(
+                "OpenCV could not encode the image as {}",
+                format.extension()
+            )));
+        }
+        Ok(buf.to_vec())
+    }
+
+    /// Write an image to `path`, choosing the format by its extension
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidParameter` for unsupported extensions,
+    /// `Error::Io` if the file cannot be written and the errors of
+    /// [`ImageWriter::encode`].
+    pub fn save(&self, image: &MatWrapper, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let format = ImageFormat::from_path(path)?;
+        let bytes = self.encode(image, format)?;
+        debug!("Saving {} bytes to {}", bytes.len(), path.display());
+        write_atomically(path, &bytes)
+    }
+
+    fn params(&self, format: ImageFormat) -> Vec<i32> {
+        match format {
+            ImageFormat::Png => vec![IMWRITE_PNG_COMPRESSION, self.png_compression.into()],
+            ImageFormat::Jpeg => vec![IMWRITE_JPEG_QUALITY, self.jpeg_quality.into()],
+            // OpenCV encodes WebP losslessly without a quality
+            ImageFormat::WebP => match self.webp_quality {
+                Some(quality) => vec![IMWRITE_WEBP_QUALITY, quality.into()],
+                None => Vec::new(),
+            },
+        }
+    }
+}
+
+/// Write `bytes` to a temporary file beside `path`, then rename it
+fn write_atomically(path: &Path, bytes: &[u8]) -> Result<()> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| Error::InvalidParameter(format!("Not a file path: {}", path.display())))?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(name);
+    temp_name.push(format!(
+        ".{}.{}.tmp",
+        process::id(),
+        TEMP_FILES.fetch_add(1, Ordering::Relaxed)
+    ));
+    let temp = path.with_file_name(temp_name);
+
+    let written = write_synced(&temp, bytes).and_then(|_| fs::rename(&temp, path));
+    if written.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    Ok(written?)
+}
+
+fn write_synced(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image_loader::ImageLoader;
+    use crate::test_support::noise_mat;
+    use opencv::core::{Vec4b, CV_8UC4};
+    use tempfile::TempDir;
+
+    fn bgra_mat() -> MatWrapper {
+        let mut mat =
+            Mat::new_rows_cols_with_default(12, 16, CV_8UC4, (10, 20, 30, 255).into()).unwrap();
+        *mat.at_2d_mut::<Vec4b>(3, 5).unwrap() = Vec4b::from([10, 20, 30, 0]);
+        MatWrapper::new(mat)
+    }
+
+    #[test]
+    fn test_extensions() {
+        assert_eq!(
+            ImageFormat::from_extension(".PNG").unwrap(),
+            ImageFormat::Png
+        );
+        assert_eq!(
+            ImageFormat::from_path("a/b.jpeg").unwrap(),
+            ImageFormat::Jpeg
+        );
+        assert_eq!(ImageFormat::from_path("b.webp").unwrap(), ImageFormat::WebP);
+        for path in ["shot.gif", "shot"] {
+            assert!(matches!(
+                ImageFormat::from_path(path),
+                Err(Error::InvalidParameter(_))
+            ));
+        }
+
+        let dir = TempDir::new().unwrap();
+        let target = dir.path().join("shot.gif");
+        let result = ImageWriter::new().save(&bgra_mat(), &target);
+        assert!(matches!(result, Err(Error::InvalidParameter(_))));
+        assert!(!target.exists());
+    }
+
+    #[test]
+    fn test_png_round_trip_keeps_alpha() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("overlay.png");
+        let image = bgra_mat();
+        ImageWriter::new()
+            .with_png_compression(9)
+            .unwrap()
+            .save(&image, &path)
+            .unwrap();
+
+        let loaded = ImageLoader::load_from_file(&path, false).unwrap();
+        assert_eq!(loaded.channels().unwrap(), 4);
+        assert_eq!(
+            *loaded.as_mat().at_2d::<Vec4b>(3, 5).unwrap(),
+            Vec4b::from([10, 20, 30, 0])
+        );
+        // Only the target is left behind
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_jpeg_quality_and_alpha() {
+        let writer = ImageWriter::new();
+        let noise = MatWrapper::new(noise_mat(64, 64, 4));
+        let best = writer.encode(&noise, ImageFormat::Jpeg).unwrap();
+        let worst = writer
+            .with_jpeg_quality(5)
+            .unwrap()
+            .encode(&noise, ImageFormat::Jpeg)
+            .unwrap();
+        assert!(worst.len() < best.len());
+
+        let bytes = writer.encode(&bgra_mat(), ImageFormat::Jpeg).unwrap();
+        let decoded = ImageLoader::load_from_memory(&bytes, false).unwrap();
+        assert_eq!(decoded.channels().unwrap(), 3);
+        assert_eq!(decoded.size().unwrap(), (16, 12));
+    }
+
+    #[test]
+    fn test_out_of_range_settings() {
+        let writer = ImageWriter::new();
+        assert!(matches!(
+            writer.with_jpeg_quality(101),
+            Err(Error::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            writer.with_png_compression(10),
+            Err(Error::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            writer.with_webp_quality(0),
+            Err(Error::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            writer.with_webp_quality(101),
+            Err(Error::InvalidParameter(_))
+        ));
+        assert_eq!(writer.with_jpeg_quality(100).unwrap().jpeg_quality(), 100);
+        assert_eq!(writer.with_png_compression(0).unwrap().png_compression(), 0);
+    }
+
+    #[test]
+    fn test_save_replaces_existing_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("shot.jpg");
+        fs::write(&path, b"old").unwrap();
+        ImageWriter::new()
+            .save(&MatWrapper::new(noise_mat(20, 10, 1)), &path)
+            .unwrap();
+        assert_eq!(ImageLoader::get_dimensions(&path).unwrap(), (20, 10));
+
+        let missing = dir.path().join("missing").join("shot.png");
+        let result = ImageWriter::new().save(&bgra_mat(), missing);
+        assert!(matches!(result, Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn test_empty_image() {
+        let result = ImageWriter::new().encode(&MatWrapper::empty().unwrap(), ImageFormat::Png);
+        assert!(matches!(result, Err(Error::InvalidParameter(_))));
+    }
+}
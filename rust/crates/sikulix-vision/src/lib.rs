@@ -2,15 +2,29 @@
 //!
 //! This crate provides template matching, image processing, and OCR capabilities.
 
+pub mod backend;
+pub mod evidence;
 pub mod finder;
+pub mod image_cache;
 pub mod image_loader;
+pub mod image_writer;
 pub mod mat_wrapper;
 pub mod matcher;
 pub mod ocr;
 pub mod resize;
+pub mod template;
 
-pub use finder::Finder;
+#[cfg(test)]
+mod test_support;
+
+pub use backend::VisionFinder;
+pub use evidence::EvidenceDir;
+pub use finder::{Finder, PyramidSearch};
+pub use image_cache::{CacheStats, ImageCache};
 pub use image_loader::ImageLoader;
-pub use mat_wrapper::MatWrapper;
-// pub use matcher::TemplateMatcher;
+pub use image_writer::{ImageFormat, ImageWriter};
+pub use mat_wrapper::{MatView, MatWrapper, PixelLayout};
+pub use matcher::TemplateMatcher;
+pub use resize::Interpolation;
+pub use template::Template;
 // pub use ocr::TextRecognizer;
@@ -1,9 +1,56 @@
 //! Safe wrapper around OpenCV Mat with automatic memory management
 
-use opencv::core::{Mat, MatTraitConst};
+use image::{DynamicImage, GrayImage, RgbImage, RgbaImage};
+use opencv::boxed_ref::BoxedRef;
+use opencv::core::{
+    AlgorithmHint, Mat, MatTraitConst, Rect, Scalar, ToInputArray, CV_8U, CV_8UC1, CV_8UC3, CV_8UC4,
+};
+use opencv::imgproc::{
+    cvt_color, COLOR_BGR2BGRA, COLOR_BGR2GRAY, COLOR_BGRA2BGR, COLOR_BGRA2GRAY, COLOR_GRAY2BGR,
+    COLOR_GRAY2BGRA,
+};
 use opencv::prelude::*;
+use sikulix_core::{Error, Region, Result, ScreenImage};
 use std::fmt;
 
+/// Byte order of the pixels in a raw buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelLayout {
+    /// One gray byte per pixel
+    Gray,
+    /// Red, green, blue
+    Rgb,
+    /// Blue, green, red, the order OpenCV uses
+    Bgr,
+    /// Red, green, blue, alpha
+    Rgba,
+    /// Blue, green, red, alpha
+    Bgra,
+    /// Blue, green, red and an unused byte, as in X11 and GDI captures
+    Bgrx,
+}
+
+impl PixelLayout {
+    /// Get the number of bytes per pixel
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            Self::Gray => 1,
+            Self::Rgb | Self::Bgr => 3,
+            Self::Rgba | Self::Bgra | Self::Bgrx => 4,
+        }
+    }
+
+    /// Get the number of channels of the matching Mat, which drops the
+    /// unused byte of `Bgrx`
+    fn channels(self) -> i32 {
+        match self {
+            Self::Gray => 1,
+            Self::Rgb | Self::Bgr | Self::Bgrx => 3,
+            Self::Rgba | Self::Bgra => 4,
+        }
+    }
+}
+
 /// Safe wrapper around OpenCV Mat with RAII memory management
 ///
 /// This wrapper ensures that:
@@ -70,6 +117,309 @@ impl MatWrapper {
         let cloned = self.as_mat().try_clone()?;
         Ok(MatWrapper::new(cloned))
     }
+
+    /// Copy a raw pixel buffer into a new BGR(A) or gray Mat
+    ///
+    /// Rows start every `stride` bytes; the last row may end right after
+    /// its pixels. RGB(A) input is reordered to BGR(A) and the unused byte
+    /// of `Bgrx` is dropped.
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidParameter` for empty dimensions, a stride
+    /// shorter than a row or a buffer too small for the image.
+    pub fn from_raw(
+        layout: PixelLayout,
+        width: u32,
+        height: u32,
+        stride: usize,
+        data: &[u8],
+    ) -> Result<Self> {
+        let bpp = layout.bytes_per_pixel();
+        let row_len = width as usize * bpp;
+        if width == 0 || height == 0 || stride < row_len {
+            return Err(Error::InvalidParameter(format!(
+                "Invalid {}x{} {:?} image with stride {}",
+                width, height, layout, stride
+            )));
+        }
+        let needed = stride * (height as usize - 1) + row_len;
+        if data.len() < needed {
+            return Err(Error::InvalidParameter(format!(
+                "Buffer of {} bytes is too small for a {}x{} {:?} image with stride {}",
+                data.len(),
+                width,
+                height,
+                layout,
+                stride
+            )));
+        }
+
+        let (cols, rows) = (to_i32(width)?, to_i32(height)?);
+        let mat_type = match layout.channels() {
+            1 => CV_8UC1,
+            3 => CV_8UC3,
+            _ => CV_8UC4,
+        };
+        let mut mat = Mat::new_rows_cols_with_default(rows, cols, mat_type, Scalar::all(0.0))?;
+        let out_len = width as usize * layout.channels() as usize;
+        let out = mat.data_bytes_mut()?;
+        for (y, target) in out.chunks_exact_mut(out_len).enumerate() {
+            let source = &data[y * stride..y * stride + row_len];
+            match layout {
+                PixelLayout::Gray | PixelLayout::Bgr | PixelLayout::Bgra => {
+                    target.copy_from_slice(source)
+                }
+                PixelLayout::Rgb | PixelLayout::Rgba => {
+                    target.copy_from_slice(source);
+                    for pixel in target.chunks_exact_mut(bpp) {
+                        pixel.swap(0, 2);
+                    }
+                }
+                PixelLayout::Bgrx => {
+                    for (pixel, bgrx) in target.chunks_exact_mut(3).zip(source.chunks_exact(4)) {
+                        pixel.copy_from_slice(&bgrx[..3]);
+                    }
+                }
+            }
+        }
+        Ok(MatWrapper::new(mat))
+    }
+
+    /// Copy a strided RGB buffer, see [`MatWrapper::from_raw`]
+    pub fn from_rgb(width: u32, height: u32, stride: usize, data: &[u8]) -> Result<Self> {
+        Self::from_raw(PixelLayout::Rgb, width, height, stride, data)
+    }
+
+    /// Copy a strided BGRA buffer, see [`MatWrapper::from_raw`]
+    pub fn from_bgra(width: u32, height: u32, stride: usize, data: &[u8]) -> Result<Self> {
+        Self::from_raw(PixelLayout::Bgra, width, height, stride, data)
+    }
+
+    /// Copy the pixels of an 8-bit gray, BGR or BGRA Mat into a tightly
+    /// packed buffer in `layout`
+    ///
+    /// Missing alpha is written as opaque, the unused byte of `Bgrx` as 255.
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidParameter` for Mats that are not 8-bit or
+    /// have another number of channels.
+    pub fn to_raw(&self, layout: PixelLayout) -> Result<Vec<u8>> {
+        let mat = self.as_mat();
+        if mat.depth() != CV_8U {
+            return Err(Error::InvalidParameter(format!(
+                "Only 8-bit images can be exported, got Mat type {}",
+                mat.typ()
+            )));
+        }
+        let converted = convert_channels(mat, layout.channels())?;
+        let (width, height) = (converted.cols() as usize, converted.rows());
+        let row_len = width * layout.channels() as usize;
+
+        let mut data = Vec::with_capacity(width * layout.bytes_per_pixel() * height as usize);
+        for y in 0..height {
+            let row = converted.row(y)?;
+            let row = &row.data_bytes()?[..row_len];
+            match layout {
+                PixelLayout::Gray | PixelLayout::Bgr | PixelLayout::Bgra => {
+                    data.extend_from_slice(row)
+                }
+                PixelLayout::Rgb | PixelLayout::Rgba => {
+                    let bpp = layout.bytes_per_pixel();
+                    for pixel in row.chunks_exact(bpp) {
+                        data.extend_from_slice(&[pixel[2], pixel[1], pixel[0]]);
+                        data.extend_from_slice(&pixel[3..]);
+                    }
+                }
+                PixelLayout::Bgrx => {
+                    for pixel in row.chunks_exact(3) {
+                        data.extend_from_slice(&[pixel[0], pixel[1], pixel[2], 255]);
+                    }
+                }
+            }
+        }
+        Ok(data)
+    }
+
+    /// Get the whole image as a region at the origin
+    pub fn bounds(&self) -> Result<Region> {
+        let (w, h) = self.size()?;
+        Ok(Region::new(0, 0, w, h))
+    }
+
+    /// Clip `region` to the image bounds
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidRegion` if no part of `region` lies inside
+    /// the image.
+    pub fn clamp_region(&self, region: Region) -> Result<Region> {
+        let bounds = self.bounds()?;
+        bounds.intersection(&region).ok_or_else(|| {
+            Error::InvalidRegion(format!(
+                "Region {:?} lies outside the image bounds {:?}",
+                region, bounds
+            ))
+        })
+    }
+
+    /// Borrow the pixels inside `region` without copying them
+    ///
+    /// The view cannot outlive this wrapper. Use `clamp_region` first for
+    /// regions that may reach beyond the image.
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidRegion` unless `region` lies inside the image.
+    pub fn view(&self, region: Region) -> Result<MatView<'_>> {
+        let rect = inner_rect(self.bounds()?, region)?;
+        Ok(MatView {
+            mat: self.as_mat().roi(rect)?,
+            region,
+        })
+    }
+
+    /// Convert to 3 channel BGR, copying the pixels
+    pub fn to_bgr(&self) -> Result<MatWrapper> {
+        convert_channels(self.as_mat(), 3).map(MatWrapper::new)
+    }
+
+    /// Convert to 4 channel BGRA, with opaque alpha if there was none
+    pub fn to_bgra(&self) -> Result<MatWrapper> {
+        convert_channels(self.as_mat(), 4).map(MatWrapper::new)
+    }
+
+    /// Convert to single channel grayscale
+    pub fn to_gray(&self) -> Result<MatWrapper> {
+        convert_channels(self.as_mat(), 1).map(MatWrapper::new)
+    }
+}
+
+/// A rectangular part of a `MatWrapper` sharing its pixels
+///
+/// Created by [`MatWrapper::view`]. The borrow keeps the parent alive, so
+/// a view can never point into freed memory.
+pub struct MatView<'a> {
+    mat: BoxedRef<'a, Mat>,
+    /// Area in the coordinates of the parent `MatWrapper`
+    region: Region,
+}
+
+impl<'a> MatView<'a> {
+    /// Get the viewed area in the coordinates of the parent `MatWrapper`
+    pub fn region(&self) -> Region {
+        self.region
+    }
+
+    /// Get the view as a Mat header for OpenCV functions
+    pub fn as_mat(&self) -> &BoxedRef<'a, Mat> {
+        &self.mat
+    }
+
+    /// Get the view dimensions (width, height)
+    pub fn size(&self) -> (i32, i32) {
+        (self.region.w, self.region.h)
+    }
+
+    /// Get the number of channels
+    pub fn channels(&self) -> i32 {
+        self.mat.channels()
+    }
+
+    /// Borrow a part of this view, with `region` relative to the view
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidRegion` unless `region` lies inside the view.
+    pub fn view(&self, region: Region) -> Result<MatView<'_>> {
+        let (w, h) = self.size();
+        let rect = inner_rect(Region::new(0, 0, w, h), region)?;
+        Ok(MatView {
+            mat: self.mat.roi(rect)?,
+            region: Region::new(
+                self.region.x + region.x,
+                self.region.y + region.y,
+                region.w,
+                region.h,
+            ),
+        })
+    }
+
+    /// Copy the viewed pixels into a new, continuous Mat
+    pub fn clone_mat(&self) -> Result<MatWrapper> {
+        Ok(MatWrapper::new(self.mat.try_clone()?))
+    }
+
+    /// Convert to 3 channel BGR, copying the pixels
+    pub fn to_bgr(&self) -> Result<MatWrapper> {
+        convert_channels(&self.mat, 3).map(MatWrapper::new)
+    }
+
+    /// Convert to 4 channel BGRA, with opaque alpha if there was none
+    pub fn to_bgra(&self) -> Result<MatWrapper> {
+        convert_channels(&self.mat, 4).map(MatWrapper::new)
+    }
+
+    /// Convert to single channel grayscale
+    pub fn to_gray(&self) -> Result<MatWrapper> {
+        convert_channels(&self.mat, 1).map(MatWrapper::new)
+    }
+}
+
+impl fmt::Debug for MatView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MatView")
+            .field("region", &self.region)
+            .field("channels", &self.channels())
+            .finish()
+    }
+}
+
+/// Check that `region` lies inside `bounds` and convert it to a rectangle
+fn inner_rect(bounds: Region, region: Region) -> Result<Rect> {
+    if bounds.intersection(&region) != Some(region) {
+        return Err(Error::InvalidRegion(format!(
+            "Region {:?} is not inside the image bounds {:?}",
+            region, bounds
+        )));
+    }
+    Ok(Rect::new(region.x, region.y, region.w, region.h))
+}
+
+/// Convert a 1, 3 or 4 channel image to 3 channel BGR
+///
+/// Three channel input is copied as-is so the result never borrows `mat`.
+pub(crate) fn to_bgr(mat: &(impl MatTraitConst + ToInputArray)) -> Result<Mat> {
+    convert_channels(mat, 3)
+}
+
+/// Convert a gray, BGR or BGRA image to `channels` channels, always copying
+pub(crate) fn convert_channels(
+    mat: &(impl MatTraitConst + ToInputArray),
+    channels: i32,
+) -> Result<Mat> {
+    let code = match (mat.channels(), channels) {
+        (from, to) if from == to && matches!(to, 1 | 3 | 4) => return Ok(mat.try_clone()?),
+        (1, 3) => COLOR_GRAY2BGR,
+        (1, 4) => COLOR_GRAY2BGRA,
+        (3, 1) => COLOR_BGR2GRAY,
+        (3, 4) => COLOR_BGR2BGRA,
+        (4, 1) => COLOR_BGRA2GRAY,
+        (4, 3) => COLOR_BGRA2BGR,
+        (from, to) => {
+            return Err(Error::InvalidParameter(format!(
+                "Unsupported channel conversion: {} to {}",
+                from, to
+            )))
+        }
+    };
+
+    let mut converted = Mat::default();
+    cvt_color(
+        mat,
+        &mut converted,
+        code,
+        0,
+        AlgorithmHint::ALGO_HINT_DEFAULT,
+    )
+    .map_err(|e| Error::Platform(format!("Failed to convert channels: {}", e)))?;
+    Ok(converted)
 }
 
 impl fmt::Debug for MatWrapper {
@@ -104,13 +454,197 @@ impl Drop for MatWrapper {
     }
 }
 
+impl TryFrom<&ScreenImage> for MatWrapper {
+    type Error = sikulix_core::Error;
+
+    /// Copy a screen capture into a CV_8UC3 (BGR) Mat
+    fn try_from(image: &ScreenImage) -> sikulix_core::Result<Self> {
+        let (width, height) = (image.width(), image.height());
+        let mut mat = Mat::new_rows_cols_with_default(height, width, CV_8UC3, Scalar::all(0.0))?;
+
+        let row_len = ScreenImage::CHANNELS * width as usize;
+        if row_len > 0 {
+            let data = mat.data_bytes_mut()?;
+            for (y, row) in data.chunks_exact_mut(row_len).enumerate() {
+                row.copy_from_slice(image.row(y as i32));
+            }
+        }
+        Ok(MatWrapper::new(mat))
+    }
+}
+
+impl TryFrom<&MatWrapper> for ScreenImage {
+    type Error = sikulix_core::Error;
+
+    /// Copy a gray, BGR or BGRA Mat into a screen image placed at the origin
+    fn try_from(wrapper: &MatWrapper) -> sikulix_core::Result<Self> {
+        let bgr = to_bgr(wrapper.as_mat())?;
+        let (width, height) = (bgr.cols(), bgr.rows());
+        let stride = ScreenImage::CHANNELS * width as usize;
+
+        let mut data = Vec::with_capacity(stride * height as usize);
+        for y in 0..height {
+            let row = bgr.row(y)?;
+            data.extend_from_slice(&row.data_bytes()?[..stride]);
+        }
+        ScreenImage::from_bgr(Region::new(0, 0, width, height), stride, data)
+    }
+}
+
+impl TryFrom<&RgbImage> for MatWrapper {
+    type Error = sikulix_core::Error;
+
+    /// Copy an `image` RGB buffer into a CV_8UC3 (BGR) Mat
+    fn try_from(image: &RgbImage) -> Result<Self> {
+        let (width, height) = image.dimensions();
+        Self::from_raw(
+            PixelLayout::Rgb,
+            width,
+            height,
+            width as usize * 3,
+            image.as_raw(),
+        )
+    }
+}
+
+impl TryFrom<&RgbaImage> for MatWrapper {
+    type Error = sikulix_core::Error;
+
+    /// Copy an `image` RGBA buffer into a CV_8UC4 (BGRA) Mat
+    fn try_from(image: &RgbaImage) -> Result<Self> {
+        let (width, height) = image.dimensions();
+        Self::from_raw(
+            PixelLayout::Rgba,
+            width,
+            height,
+            width as usize * 4,
+            image.as_raw(),
+        )
+    }
+}
+
+impl TryFrom<&GrayImage> for MatWrapper {
+    type Error = sikulix_core::Error;
+
+    /// Copy an `image` gray buffer into a CV_8UC1 Mat
+    fn try_from(image: &GrayImage) -> Result<Self> {
+        let (width, height) = image.dimensions();
+        Self::from_raw(
+            PixelLayout::Gray,
+            width,
+            height,
+            width as usize,
+            image.as_raw(),
+        )
+    }
+}
+
+impl TryFrom<&DynamicImage> for MatWrapper {
+    type Error = sikulix_core::Error;
+
+    /// Copy an 8-bit image, keeping its channels
+    ///
+    /// Gray with alpha becomes BGRA. Images with 16-bit or float channels
+    /// are rejected, as a conversion would lose precision.
+    fn try_from(image: &DynamicImage) -> Result<Self> {
+        match image {
+            DynamicImage::ImageLuma8(gray) => gray.try_into(),
+            DynamicImage::ImageRgb8(rgb) => rgb.try_into(),
+            DynamicImage::ImageRgba8(rgba) => rgba.try_into(),
+            DynamicImage::ImageLumaA8(_) => (&image.to_rgba8()).try_into(),
+            other => Err(Error::InvalidParameter(format!(
+                "Unsupported pixel type {:?}, convert to 8 bits per channel first",
+                other.color()
+            ))),
+        }
+    }
+}
+
+impl TryFrom<&MatWrapper> for DynamicImage {
+    type Error = sikulix_core::Error;
+
+    /// Copy an 8-bit gray, BGR or BGRA Mat into a Luma8, Rgb8 or Rgba8
+    /// image
+    fn try_from(wrapper: &MatWrapper) -> Result<Self> {
+        let (width, height) = wrapper.size()?;
+        let (width, height) = (width as u32, height as u32);
+        let image = match wrapper.channels()? {
+            1 => GrayImage::from_raw(width, height, wrapper.to_raw(PixelLayout::Gray)?)
+                .map(DynamicImage::ImageLuma8),
+            3 => RgbImage::from_raw(width, height, wrapper.to_raw(PixelLayout::Rgb)?)
+                .map(DynamicImage::ImageRgb8),
+            4 => RgbaImage::from_raw(width, height, wrapper.to_raw(PixelLayout::Rgba)?)
+                .map(DynamicImage::ImageRgba8),
+            n => {
+                return Err(Error::InvalidParameter(format!(
+                    "Unsupported channel count: {}",
+                    n
+                )))
+            }
+        };
+        image.ok_or_else(|| Error::Platform("Pixel buffer does not match the image size".into()))
+    }
+}
+
+impl TryFrom<&MatWrapper> for RgbaImage {
+    type Error = sikulix_core::Error;
+
+    /// Copy an 8-bit gray, BGR or BGRA Mat, with opaque alpha if it has none
+    fn try_from(wrapper: &MatWrapper) -> Result<Self> {
+        let (width, height) = wrapper.size()?;
+        RgbaImage::from_raw(
+            width as u32,
+            height as u32,
+            wrapper.to_raw(PixelLayout::Rgba)?,
+        )
+        .ok_or_else(|| Error::Platform("Pixel buffer does not match the image size".into()))
+    }
+}
+
+impl TryFrom<&MatWrapper> for RgbImage {
+    type Error = sikulix_core::Error;
+
+    /// Copy an 8-bit gray, BGR or BGRA Mat, dropping alpha if it has one
+    fn try_from(wrapper: &MatWrapper) -> Result<Self> {
+        let (width, height) = wrapper.size()?;
+        RgbImage::from_raw(
+            width as u32,
+            height as u32,
+            wrapper.to_raw(PixelLayout::Rgb)?,
+        )
+        .ok_or_else(|| Error::Platform("Pixel buffer does not match the image size".into()))
+    }
+}
+
+impl TryFrom<&MatWrapper> for GrayImage {
+    type Error = sikulix_core::Error;
+
+    /// Copy an 8-bit gray, BGR or BGRA Mat, converting colors to gray
+    fn try_from(wrapper: &MatWrapper) -> Result<Self> {
+        let (width, height) = wrapper.size()?;
+        GrayImage::from_raw(
+            width as u32,
+            height as u32,
+            wrapper.to_raw(PixelLayout::Gray)?,
+        )
+        .ok_or_else(|| Error::Platform("Pixel buffer does not match the image size".into()))
+    }
+}
+
+/// Convert an image dimension to OpenCV's `i32`
+fn to_i32(value: u32) -> Result<i32> {
+    i32::try_from(value)
+        .map_err(|_| Error::InvalidParameter(format!("Image dimension {} is too large", value)))
+}
+
 // Prevent automatic cloning (Mat cloning is expensive)
 // Users must explicitly call clone_mat()
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use opencv::core::{Mat, CV_8UC3};
+    use image::{ImageBuffer, Luma, LumaA, Rgb, Rgba};
+    use opencv::core::{Vec3b, Vec4b, CV_8UC2};
 
     #[test]
     fn test_create_empty() {
@@ -158,6 +692,191 @@ mod tests {
         assert_eq!(size_before, (size_after.width, size_after.height));
     }
 
+    #[test]
+    fn test_from_screen_image() {
+        let data = vec![1, 2, 3, 4, 5, 6, 0, 0, 7, 8, 9, 10, 11, 12, 0, 0];
+        let region = sikulix_core::Region::new(0, 0, 2, 2);
+        let capture = ScreenImage::from_bgr(region, 8, data).unwrap();
+
+        let wrapper = MatWrapper::try_from(&capture).unwrap();
+        assert_eq!(wrapper.size().unwrap(), (2, 2));
+        assert_eq!(wrapper.mat_type().unwrap(), CV_8UC3);
+        assert_eq!(
+            wrapper.as_mat().data_bytes().unwrap(),
+            &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]
+        );
+    }
+
+    #[test]
+    fn test_into_screen_image() {
+        let gray = Mat::new_rows_cols_with_default(2, 3, CV_8UC1, Scalar::all(7.0)).unwrap();
+        let image = ScreenImage::try_from(&MatWrapper::new(gray)).unwrap();
+        assert_eq!(image.region(), Region::new(0, 0, 3, 2));
+        assert_eq!(image.pixel(2, 1), Some([7, 7, 7]));
+    }
+
+    #[test]
+    fn test_view_borrows_parent_pixels() {
+        let wrapper = MatWrapper::new(crate::test_support::noise_mat(40, 30, 5));
+        let view = wrapper.view(Region::new(10, 5, 20, 15)).unwrap();
+        assert_eq!(view.size(), (20, 15));
+        assert_eq!(view.channels(), 3);
+
+        // Same memory as the parent, not a copy
+        let parent = wrapper.as_mat().at_2d::<Vec3b>(7, 12).unwrap() as *const Vec3b;
+        let viewed = view.as_mat().at_2d::<Vec3b>(2, 2).unwrap() as *const Vec3b;
+        assert_eq!(parent, viewed);
+
+        let inner = view.view(Region::new(2, 2, 4, 4)).unwrap();
+        assert_eq!(inner.region(), Region::new(12, 7, 4, 4));
+        let copy = inner.clone_mat().unwrap();
+        assert_eq!(
+            *copy.as_mat().at_2d::<Vec3b>(0, 0).unwrap(),
+            *wrapper.as_mat().at_2d::<Vec3b>(7, 12).unwrap()
+        );
+
+        for outside in [Region::new(30, 0, 11, 5), Region::new(-1, 0, 5, 5)] {
+            assert!(matches!(
+                wrapper.view(outside),
+                Err(Error::InvalidRegion(_))
+            ));
+        }
+        assert!(matches!(
+            view.view(Region::new(0, 0, 21, 1)),
+            Err(Error::InvalidRegion(_))
+        ));
+    }
+
+    #[test]
+    fn test_clamp_region() {
+        let wrapper = MatWrapper::new(
+            Mat::new_rows_cols_with_default(30, 40, CV_8UC3, Scalar::all(0.0)).unwrap(),
+        );
+        assert_eq!(
+            wrapper.clamp_region(Region::new(-5, 20, 20, 20)).unwrap(),
+            Region::new(0, 20, 15, 10)
+        );
+        assert_eq!(
+            wrapper.clamp_region(Region::new(0, 0, 40, 30)).unwrap(),
+            Region::new(0, 0, 40, 30)
+        );
+        for outside in [Region::new(40, 0, 5, 5), Region::new(0, -10, 5, 10)] {
+            assert!(matches!(
+                wrapper.clamp_region(outside),
+                Err(Error::InvalidRegion(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_channel_conversions() {
+        let gray = MatWrapper::new(
+            Mat::new_rows_cols_with_default(2, 3, CV_8UC1, Scalar::all(7.0)).unwrap(),
+        );
+        let bgra = gray.to_bgra().unwrap();
+        assert_eq!(bgra.mat_type().unwrap(), CV_8UC4);
+        assert_eq!(
+            *bgra.as_mat().at_2d::<Vec4b>(1, 2).unwrap(),
+            Vec4b::from([7, 7, 7, 255])
+        );
+        assert_eq!(bgra.to_bgr().unwrap().mat_type().unwrap(), CV_8UC3);
+        assert_eq!(bgra.to_gray().unwrap().mat_type().unwrap(), CV_8UC1);
+
+        let view = bgra.view(Region::new(1, 0, 2, 2)).unwrap();
+        let bgr = view.to_bgr().unwrap();
+        assert_eq!(bgr.size().unwrap(), (2, 2));
+        assert_eq!(view.to_gray().unwrap().channels().unwrap(), 1);
+
+        let two = MatWrapper::new(
+            Mat::new_rows_cols_with_default(2, 2, CV_8UC2, Scalar::all(0.0)).unwrap(),
+        );
+        assert!(matches!(two.to_bgr(), Err(Error::InvalidParameter(_))));
+    }
+
+    #[test]
+    fn test_from_strided_raw_buffers() {
+        // Two RGB pixels per row, padded to 8 bytes, last row unpadded
+        let rgb = [1, 2, 3, 4, 5, 6, 0, 0, 7, 8, 9, 10, 11, 12];
+        let wrapper = MatWrapper::from_rgb(2, 2, 8, &rgb).unwrap();
+        assert_eq!(wrapper.mat_type().unwrap(), CV_8UC3);
+        assert_eq!(
+            wrapper.as_mat().data_bytes().unwrap(),
+            &[3, 2, 1, 6, 5, 4, 9, 8, 7, 12, 11, 10]
+        );
+
+        let bgra = [1, 2, 3, 4, 5, 6, 7, 8];
+        let wrapper = MatWrapper::from_bgra(1, 2, 4, &bgra).unwrap();
+        assert_eq!(wrapper.mat_type().unwrap(), CV_8UC4);
+        assert_eq!(wrapper.as_mat().data_bytes().unwrap(), &bgra);
+
+        let wrapper = MatWrapper::from_raw(PixelLayout::Bgrx, 2, 1, 8, &bgra).unwrap();
+        assert_eq!(wrapper.as_mat().data_bytes().unwrap(), &[1, 2, 3, 5, 6, 7]);
+        assert_eq!(
+            wrapper.to_raw(PixelLayout::Bgrx).unwrap(),
+            vec![1, 2, 3, 255, 5, 6, 7, 255]
+        );
+
+        for (width, height, stride) in [(2, 2, 5), (0, 2, 8), (2, 3, 8)] {
+            assert!(matches!(
+                MatWrapper::from_rgb(width, height, stride, &rgb),
+                Err(Error::InvalidParameter(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_image_crate_round_trips() {
+        let rgba = ImageBuffer::from_fn(3, 2, |x, y| Rgba([x as u8, y as u8, 200, 10 * x as u8]));
+        let wrapper = MatWrapper::try_from(&rgba).unwrap();
+        assert_eq!(
+            *wrapper.as_mat().at_2d::<Vec4b>(1, 2).unwrap(),
+            Vec4b::from([200, 1, 2, 20])
+        );
+        assert_eq!(RgbaImage::try_from(&wrapper).unwrap(), rgba);
+
+        let rgb = DynamicImage::ImageRgb8(ImageBuffer::from_fn(3, 2, |x, y| {
+            Rgb([x as u8, y as u8, 7])
+        }));
+        let wrapper = MatWrapper::try_from(&rgb).unwrap();
+        assert_eq!(
+            *wrapper.as_mat().at_2d::<Vec3b>(1, 2).unwrap(),
+            Vec3b::from([7, 1, 2])
+        );
+        assert_eq!(DynamicImage::try_from(&wrapper).unwrap(), rgb);
+        assert_eq!(RgbImage::try_from(&wrapper).unwrap(), rgb.to_rgb8());
+
+        let gray = DynamicImage::ImageLuma8(ImageBuffer::from_fn(3, 2, |x, _| Luma([x as u8])));
+        let wrapper = MatWrapper::try_from(&gray).unwrap();
+        assert_eq!(wrapper.mat_type().unwrap(), CV_8UC1);
+        assert_eq!(DynamicImage::try_from(&wrapper).unwrap(), gray);
+        assert_eq!(GrayImage::try_from(&wrapper).unwrap(), gray.to_luma8());
+
+        // BGR has no alpha, so the RGBA copy is opaque
+        let opaque = RgbaImage::try_from(&MatWrapper::try_from(&rgb).unwrap()).unwrap();
+        assert_eq!(opaque.get_pixel(2, 1), &Rgba([2, 1, 7, 255]));
+    }
+
+    #[test]
+    fn test_image_crate_pixel_types() {
+        let gray_alpha = DynamicImage::ImageLumaA8(ImageBuffer::from_pixel(2, 2, LumaA([9, 99])));
+        let wrapper = MatWrapper::try_from(&gray_alpha).unwrap();
+        assert_eq!(
+            *wrapper.as_mat().at_2d::<Vec4b>(0, 0).unwrap(),
+            Vec4b::from([9, 9, 9, 99])
+        );
+
+        let deep = DynamicImage::ImageLuma16(ImageBuffer::from_pixel(2, 2, Luma([1000u16])));
+        assert!(matches!(
+            MatWrapper::try_from(&deep),
+            Err(Error::InvalidParameter(_))
+        ));
+
+        let two = MatWrapper::new(
+            Mat::new_rows_cols_with_default(2, 2, CV_8UC2, Scalar::all(0.0)).unwrap(),
+        );
+        assert!(DynamicImage::try_from(&two).is_err());
+    }
+
     #[test]
     fn test_debug_format() {
         let mat = Mat::new_rows_cols_with_default(10, 20, CV_8UC3, (0, 0, 0, 0).into()).unwrap();
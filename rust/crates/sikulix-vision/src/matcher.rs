@@ -1,3 +1,440 @@
 //! OpenCV template matching wrapper
 
-// Placeholder - will implement in Phase 1
+use opencv::core::{
+    bitwise_not, count_non_zero, mean_std_dev, min_max_loc, no_array, Mat, Point, ToInputArray,
+};
+use opencv::imgproc::{
+    match_template, TM_CCOEFF, TM_CCOEFF_NORMED, TM_CCORR, TM_CCORR_NORMED, TM_SQDIFF,
+    TM_SQDIFF_NORMED,
+};
+use opencv::prelude::*;
+use sikulix_core::{Error, MatchMethod, Result};
+use tracing::trace;
+
+/// Best position found in a similarity map
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MatchPeak {
+    /// X offset of the template's top-left corner in the searched image
+    pub x: i32,
+
+    /// Y offset of the template's top-left corner in the searched image
+    pub y: i32,
+
+    /// Similarity score (0.0 to 1.0)
+    pub score: f64,
+}
+
+/// Runs OpenCV template matching and extracts scores from the result map
+///
+/// The haystack and template must share the same depth and channel count.
+/// Scores are normalised to the 0.0 - 1.0 range used by `Match::score`.
+pub struct TemplateMatcher;
+
+impl TemplateMatcher {
+    /// Compute the TM_CCOEFF_NORMED similarity map of `template` over `haystack`
+    ///
+    /// The result is a single-channel CV_32F map of size
+    /// `(W - w + 1) x (H - h + 1)`, where each value is the score of the
+    /// template placed with its top-left corner at that position.
+    pub fn similarity_map(
+        haystack: &impl ToInputArray,
+        template: &impl ToInputArray,
+    ) -> Result<Mat> {
+        Self::raw_map(haystack, template, None, MatchMethod::CCoeffNormed)
+    }
+
+    /// Compute a map of 0.0 - 1.0 scores of `template` over `haystack` using `method`
+    ///
+    /// Only the template pixels where `mask` (single channel CV_8U, template
+    /// sized) is nonzero are compared. Images are expected to be 8-bit; the
+    /// raw values of the non-normalised methods are scaled by the largest
+    /// value 8-bit pixels can produce. Positions OpenCV cannot score
+    /// (NaN/infinite values) get 0.0.
+    ///
+    /// A template of one plain color is matched by `SqDiffNormed` unless
+    /// `SqDiff` was requested, as in the Java `Finder2`: correlation is
+    /// undefined for a template without variance. A plain black template
+    /// and the haystack are inverted first, because the normalised squared
+    /// difference divides by the template's energy.
+    pub fn score_map(
+        haystack: &impl ToInputArray,
+        template: &Mat,
+        mask: Option<&Mat>,
+        method: MatchMethod,
+    ) -> Result<Mat> {
+        let color = PlainColor::of(template, mask)?;
+        let method = match color {
+            Some(_) if method != MatchMethod::SqDiff => MatchMethod::SqDiffNormed,
+            _ => method,
+        };
+
+        let mut result = match color {
+            Some(PlainColor { black: true }) if method == MatchMethod::SqDiffNormed => {
+                trace!("Plain black template, matching inverted images");
+                Self::raw_map(&invert(haystack)?, &invert(template)?, mask, method)?
+            }
+            _ => Self::raw_map(haystack, template, mask, method)?,
+        };
+
+        let pixels = match mask {
+            Some(mask) => count_non_zero(mask)?,
+            None => template.rows() * template.cols(),
+        };
+        let samples = pixels as f64 * template.channels() as f64;
+        to_scores(&mut result, method, samples)?;
+        Ok(result)
+    }
+
+    /// Run OpenCV matchTemplate, returning the raw result map of `method`
+    fn raw_map(
+        haystack: &impl ToInputArray,
+        template: &impl ToInputArray,
+        mask: Option<&Mat>,
+        method: MatchMethod,
+    ) -> Result<Mat> {
+        let mut result = Mat::default();
+        let code = cv_method(method);
+        match mask {
+            Some(mask) => match_template(haystack, template, &mut result, code, mask),
+            None => match_template(haystack, template, &mut result, code, &no_array()),
+        }
+        .map_err(|e| Error::Platform(format!("OpenCV matchTemplate failed: {}", e)))?;
+        Ok(result)
+    }
+
+    /// Find the position with the highest score in a similarity map
+    pub fn best_peak(result: &Mat) -> Result<MatchPeak> {
+        let mut max_val = 0.0f64;
+        let mut max_loc = Point::default();
+        min_max_loc(
+            result,
+            None,
+            Some(&mut max_val),
+            None,
+            Some(&mut max_loc),
+            &no_array(),
+        )
+        .map_err(|e| Error::Platform(format!("OpenCV minMaxLoc failed: {}", e)))?;
+
+        let score = Self::normalize_score(max_val);
+        trace!(
+            "Best peak at ({}, {}) score {:.4}",
+            max_loc.x,
+            max_loc.y,
+            score
+        );
+
+        Ok(MatchPeak {
+            x: max_loc.x,
+            y: max_loc.y,
+            score,
+        })
+    }
+
+    /// Collect the local maxima of a similarity map that score at least `threshold`
+    ///
+    /// A position is a local maximum when none of its 8 neighbours scores
+    /// higher. Plateaus yield several adjacent peaks; callers are expected
+    /// to collapse those with non-maximum suppression.
+    pub fn peaks_above(result: &Mat, threshold: f64) -> Result<Vec<MatchPeak>> {
+        let rows = result.rows();
+        let mut peaks = Vec::new();
+
+        for y in 0..rows {
+            let row = result.at_row::<f32>(y)?;
+            let above = if y > 0 {
+                Some(result.at_row::<f32>(y - 1)?)
+            } else {
+                None
+            };
+            let below = if y + 1 < rows {
+                Some(result.at_row::<f32>(y + 1)?)
+            } else {
+                None
+            };
+
+            for (x, &value) in row.iter().enumerate() {
+                let score = Self::normalize_score(value as f64);
+                if score < threshold {
+                    continue;
+                }
+
+                let lo = x.saturating_sub(1);
+                let hi = (x + 2).min(row.len());
+                let is_peak = [above, Some(row), below]
+                    .iter()
+                    .flatten()
+                    .all(|r| r[lo..hi].iter().all(|&n| n <= value || n.is_nan()));
+
+                if is_peak {
+                    peaks.push(MatchPeak {
+                        x: x as i32,
+                        y,
+                        score,
+                    });
+                }
+            }
+        }
+
+        trace!("{} peaks at or above {:.2}", peaks.len(), threshold);
+        Ok(peaks)
+    }
+
+    /// Match `template` against `haystack` and return the single best position
+    pub fn find_best(
+        haystack: &impl ToInputArray,
+        template: &impl ToInputArray,
+    ) -> Result<MatchPeak> {
+        let result = Self::similarity_map(haystack, template)?;
+        Self::best_peak(&result)
+    }
+
+    /// Clamp a raw score into the 0.0 - 1.0 score range
+    ///
+    /// Negative correlation is reported as 0.0, and NaN/infinite values
+    /// (which OpenCV produces for uniform image patches) as no match at all.
+    pub fn normalize_score(raw: f64) -> f64 {
+        if raw.is_finite() {
+            raw.clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Standard deviation (summed over channels) below which a template is one plain color
+const PLAIN_COLOR_STDDEV: f64 = 1e-5;
+
+/// Mean (summed over channels) below which a plain color is black
+const BLACK_MEAN: f64 = 1e-5;
+
+/// A template consisting of a single color
+#[derive(Debug, Clone, Copy)]
+struct PlainColor {
+    /// The color is black
+    black: bool,
+}
+
+impl PlainColor {
+    /// Check if the (unmasked pixels of the) template have a single color
+    fn of(template: &Mat, mask: Option<&Mat>) -> Result<Option<PlainColor>> {
+        let mut mean = Mat::default();
+        let mut stddev = Mat::default();
+        match mask {
+            Some(mask) => mean_std_dev(template, &mut mean, &mut stddev, mask),
+            None => mean_std_dev(template, &mut mean, &mut stddev, &no_array()),
+        }
+        .map_err(|e| Error::Platform(format!("OpenCV meanStdDev failed: {}", e)))?;
+
+        let (mut mean_sum, mut stddev_sum) = (0.0, 0.0);
+        for channel in 0..template.channels() {
+            mean_sum += *mean.at::<f64>(channel)?;
+            stddev_sum += *stddev.at::<f64>(channel)?;
+        }
+
+        if stddev_sum >= PLAIN_COLOR_STDDEV {
+            return Ok(None);
+        }
+        Ok(Some(PlainColor {
+            black: mean_sum < BLACK_MEAN,
+        }))
+    }
+}
+
+/// Map a match method to its OpenCV `TM_*` code
+fn cv_method(method: MatchMethod) -> i32 {
+    match method {
+        MatchMethod::SqDiff => TM_SQDIFF,
+        MatchMethod::SqDiffNormed => TM_SQDIFF_NORMED,
+        MatchMethod::CCorr => TM_CCORR,
+        MatchMethod::CCorrNormed => TM_CCORR_NORMED,
+        MatchMethod::CCoeff => TM_CCOEFF,
+        MatchMethod::CCoeffNormed => TM_CCOEFF_NORMED,
+    }
+}
+
+/// Invert an 8-bit image, so black becomes white
+fn invert(image: &impl ToInputArray) -> Result<Mat> {
+    let mut inverted = Mat::default();
+    bitwise_not(image, &mut inverted, &no_array())
+        .map_err(|e| Error::Platform(format!("Failed to invert image: {}", e)))?;
+    Ok(inverted)
+}
+
+/// Convert a raw result map of `method` into scores in place
+///
+/// `samples` is the number of compared values (template pixels times
+/// channels). Unbounded raw values are divided by their 8-bit maximum:
+/// `samples * 255^2` for the squared difference and cross correlation,
+/// a quarter of that for the correlation coefficient. The squared
+/// difference is turned into a score through the root mean square error.
+fn to_scores(result: &mut Mat, method: MatchMethod, samples: f64) -> Result<()> {
+    let range = samples * 255.0 * 255.0;
+    for y in 0..result.rows() {
+        for value in result.at_row_mut::<f32>(y)? {
+            let raw = *value as f64;
+            let score = match method {
+                MatchMethod::SqDiff => 1.0 - (raw.max(0.0) / range).sqrt(),
+                MatchMethod::SqDiffNormed => 1.0 - raw,
+                MatchMethod::CCorr => raw / range,
+                MatchMethod::CCoeff => raw / (range / 4.0),
+                MatchMethod::CCorrNormed | MatchMethod::CCoeffNormed => raw,
+            };
+            *value = TemplateMatcher::normalize_score(score) as f32;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::noise_mat;
+    use opencv::core::{Rect, Scalar, CV_32FC1, CV_8UC1, CV_8UC3};
+
+    #[test]
+    fn test_similarity_map_size() {
+        let haystack = noise_mat(40, 30, 1);
+        let template = noise_mat(10, 5, 2);
+
+        let result = TemplateMatcher::similarity_map(&haystack, &template).unwrap();
+        assert_eq!(result.cols(), 31);
+        assert_eq!(result.rows(), 26);
+    }
+
+    #[test]
+    fn test_find_best_exact_crop() {
+        let haystack = noise_mat(60, 40, 7);
+        let template = haystack
+            .roi(Rect::new(23, 11, 12, 9))
+            .unwrap()
+            .try_clone()
+            .unwrap();
+
+        let peak = TemplateMatcher::find_best(&haystack, &template).unwrap();
+        assert_eq!((peak.x, peak.y), (23, 11));
+        assert!(peak.score > 0.99);
+    }
+
+    #[test]
+    fn test_masked_match_ignores_hidden_pixels() {
+        let mut haystack = noise_mat(60, 40, 3);
+        let template = noise_mat(12, 12, 4);
+        // Only the left half of the template is visible on screen
+        let visible = Rect::new(0, 0, 6, 12);
+        {
+            let mut target = haystack.roi_mut(Rect::new(30, 20, 6, 12)).unwrap();
+            template.roi(visible).unwrap().copy_to(&mut target).unwrap();
+        }
+        let mut mask = Mat::new_rows_cols_with_default(12, 12, CV_8UC1, Scalar::all(0.0)).unwrap();
+        {
+            let mut shown = mask.roi_mut(visible).unwrap();
+            shown.set_to(&Scalar::all(255.0), &no_array()).unwrap();
+        }
+
+        let result =
+            TemplateMatcher::score_map(&haystack, &template, Some(&mask), MatchMethod::default())
+                .unwrap();
+        let peak = TemplateMatcher::best_peak(&result).unwrap();
+        assert_eq!((peak.x, peak.y), (30, 20));
+        assert!(peak.score > 0.99);
+
+        let unmasked = TemplateMatcher::find_best(&haystack, &template).unwrap();
+        assert!(unmasked.score < 0.8);
+    }
+
+    #[test]
+    fn test_every_method_scores_exact_crop() {
+        let haystack = noise_mat(50, 40, 13);
+        let template = haystack
+            .roi(Rect::new(17, 9, 10, 8))
+            .unwrap()
+            .try_clone()
+            .unwrap();
+
+        for method in [
+            MatchMethod::SqDiff,
+            MatchMethod::SqDiffNormed,
+            MatchMethod::CCorr,
+            MatchMethod::CCorrNormed,
+            MatchMethod::CCoeff,
+            MatchMethod::CCoeffNormed,
+        ] {
+            let result = TemplateMatcher::score_map(&haystack, &template, None, method).unwrap();
+            let peak = TemplateMatcher::best_peak(&result).unwrap();
+            assert!((0.0..=1.0).contains(&peak.score), "{:?}", method);
+
+            // The raw correlations favour bright / high contrast areas
+            if method.is_normed() || method.is_difference() {
+                assert_eq!((peak.x, peak.y), (17, 9), "{:?}", method);
+                assert!(peak.score > 0.99, "{:?}", method);
+            }
+        }
+    }
+
+    /// Noise image with plain color rectangles
+    fn noise_with_panels(panels: &[(Rect, Scalar)]) -> Mat {
+        let mut image = noise_mat(100, 60, 17);
+        for &(rect, color) in panels {
+            let mut panel = image.roi_mut(rect).unwrap();
+            panel.set_to(&color, &no_array()).unwrap();
+        }
+        image
+    }
+
+    #[test]
+    fn test_plain_color_template() {
+        let blue = Scalar::new(200.0, 120.0, 40.0, 0.0);
+        let orange = Scalar::new(40.0, 120.0, 200.0, 0.0);
+        let haystack = noise_with_panels(&[
+            (Rect::new(5, 5, 20, 20), orange),
+            (Rect::new(60, 30, 30, 20), blue),
+        ]);
+        let template = Mat::new_rows_cols_with_default(10, 10, CV_8UC3, blue).unwrap();
+
+        let result =
+            TemplateMatcher::score_map(&haystack, &template, None, MatchMethod::CCoeffNormed)
+                .unwrap();
+        let peak = TemplateMatcher::best_peak(&result).unwrap();
+        assert!((60..=80).contains(&peak.x) && (30..=40).contains(&peak.y));
+        assert!(peak.score > 0.99);
+
+        let on_orange = *result.at_2d::<f32>(10, 10).unwrap();
+        assert!(on_orange < 0.9);
+    }
+
+    #[test]
+    fn test_plain_black_template() {
+        let haystack = noise_with_panels(&[(Rect::new(40, 20, 12, 12), Scalar::all(0.0))]);
+        let template = Mat::new_rows_cols_with_default(8, 8, CV_8UC3, Scalar::all(0.0)).unwrap();
+
+        let result =
+            TemplateMatcher::score_map(&haystack, &template, None, MatchMethod::default()).unwrap();
+        let peak = TemplateMatcher::best_peak(&result).unwrap();
+        assert!((40..=44).contains(&peak.x) && (20..=24).contains(&peak.y));
+        assert!(peak.score > 0.99);
+    }
+
+    #[test]
+    fn test_peaks_above_threshold() {
+        let mut result = Mat::new_rows_cols_with_default(5, 6, CV_32FC1, 0.1.into()).unwrap();
+        *result.at_2d_mut::<f32>(1, 1).unwrap() = 0.95;
+        *result.at_2d_mut::<f32>(1, 2).unwrap() = 0.90;
+        *result.at_2d_mut::<f32>(3, 4).unwrap() = 0.85;
+        *result.at_2d_mut::<f32>(4, 0).unwrap() = 0.50;
+
+        let peaks = TemplateMatcher::peaks_above(&result, 0.8).unwrap();
+        let positions: Vec<_> = peaks.iter().map(|p| (p.x, p.y)).collect();
+        assert_eq!(positions, vec![(1, 1), (4, 3)]);
+        assert!((peaks[0].score - 0.95).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_normalize_score() {
+        assert_eq!(TemplateMatcher::normalize_score(0.85), 0.85);
+        assert_eq!(TemplateMatcher::normalize_score(-0.4), 0.0);
+        assert_eq!(TemplateMatcher::normalize_score(1.0000001), 1.0);
+        assert_eq!(TemplateMatcher::normalize_score(f64::NAN), 0.0);
+        assert_eq!(TemplateMatcher::normalize_score(f64::INFINITY), 0.0);
+    }
+}
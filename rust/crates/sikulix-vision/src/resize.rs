@@ -1,3 +1,338 @@
 //! Image resizing and preprocessing
+//!
+//! Resizing is available as methods on [`MatWrapper`]: to an exact size
+//! ([`MatWrapper::resize`]), by a factor ([`MatWrapper::scale`]) or into a
+//! bounding box keeping the aspect ratio ([`MatWrapper::fit_within`]).
+//! Gaussian pyramids halve or double images with smoothing, which is what
+//! coarse-to-fine searches work on.
 
-// Placeholder - will implement in Phase 1
+use crate::mat_wrapper::MatWrapper;
+use opencv::core::{Mat, Size, BORDER_DEFAULT};
+use opencv::imgproc::{
+    pyr_down, pyr_up, resize, INTER_AREA, INTER_CUBIC, INTER_LINEAR, INTER_NEAREST,
+};
+use opencv::prelude::*;
+use sikulix_core::{Error, Result};
+
+/// How pixels are computed when resizing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interpolation {
+    /// Copy the nearest source pixel; keeps hard edges, fastest
+    Nearest,
+    /// Bilinear, a good default for enlarging
+    #[default]
+    Linear,
+    /// Bicubic over 4x4 neighbourhoods; sharper but slower than linear
+    Cubic,
+    /// Average the covered source pixels; best for shrinking
+    Area,
+}
+
+impl Interpolation {
+    /// Get the OpenCV `INTER_*` flag
+    pub fn to_opencv(self) -> i32 {
+        match self {
+            Self::Nearest => INTER_NEAREST,
+            Self::Linear => INTER_LINEAR,
+            Self::Cubic => INTER_CUBIC,
+            Self::Area => INTER_AREA,
+        }
+    }
+
+    /// Pick area interpolation for shrinking and bilinear for enlarging
+    pub fn for_factor(factor: f64) -> Self {
+        if factor < 1.0 {
+            Self::Area
+        } else {
+            Self::Linear
+        }
+    }
+}
+
+impl MatWrapper {
+    /// Resize to exactly `width` x `height`
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidParameter` for an empty image or target size.
+    pub fn resize(
+        &self,
+        width: i32,
+        height: i32,
+        interpolation: Interpolation,
+    ) -> Result<MatWrapper> {
+        if width <= 0 || height <= 0 {
+            return Err(Error::InvalidParameter(format!(
+                "Target size must be positive, got {}x{}",
+                width, height
+            )));
+        }
+        if self.is_empty() {
+            return Err(Error::InvalidParameter(
+                "Cannot resize an empty image".to_string(),
+            ));
+        }
+
+        let mut resized = Mat::default();
+        resize(
+            self.as_mat(),
+            &mut resized,
+            Size::new(width, height),
+            0.0,
+            0.0,
+            interpolation.to_opencv(),
+        )
+        .map_err(|e| Error::Platform(format!("Failed to resize image: {}", e)))?;
+        Ok(MatWrapper::new(resized))
+    }
+
+    /// Resize by a uniform scale factor
+    ///
+    /// Each side is rounded to the nearest pixel and is at least 1.
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidParameter` unless `factor` is positive and
+    /// finite.
+    pub fn scale(&self, factor: f64, interpolation: Interpolation) -> Result<MatWrapper> {
+        if !factor.is_finite() || factor <= 0.0 {
+            return Err(Error::InvalidParameter(format!(
+                "Scale factor must be positive, got {}",
+                factor
+            )));
+        }
+        let (w, h) = self.size()?;
+        self.resize(scaled(w, factor), scaled(h, factor), interpolation)
+    }
+
+    /// Resize to the largest size fitting into `max_width` x `max_height`
+    /// that keeps the aspect ratio
+    ///
+    /// Images are enlarged as well as shrunk to touch the box.
+    pub fn fit_within(
+        &self,
+        max_width: i32,
+        max_height: i32,
+        interpolation: Interpolation,
+    ) -> Result<MatWrapper> {
+        if max_width <= 0 || max_height <= 0 {
+            return Err(Error::InvalidParameter(format!(
+                "Bounding box must be positive, got {}x{}",
+                max_width, max_height
+            )));
+        }
+        let (w, h) = self.size()?;
+        if w == 0 || h == 0 {
+            return Err(Error::InvalidParameter(
+                "Cannot resize an empty image".to_string(),
+            ));
+        }
+        let factor = (max_width as f64 / w as f64).min(max_height as f64 / h as f64);
+        let width = scaled(w, factor).min(max_width);
+        let height = scaled(h, factor).min(max_height);
+        self.resize(width, height, interpolation)
+    }
+
+    /// Blur and halve the image, one level down a Gaussian pyramid
+    ///
+    /// Odd sides round up, so a 5x3 image becomes 3x2.
+    pub fn pyr_down(&self) -> Result<MatWrapper> {
+        let (w, h) = self.size()?;
+        if w == 0 || h == 0 {
+            return Err(Error::InvalidParameter(
+                "Cannot downsample an empty image".to_string(),
+            ));
+        }
+        let mut down = Mat::default();
+        pyr_down(
+            self.as_mat(),
+            &mut down,
+            Size::new((w + 1) / 2, (h + 1) / 2),
+            BORDER_DEFAULT,
+        )
+        .map_err(|e| Error::Platform(format!("Failed to downsample image: {}", e)))?;
+        Ok(MatWrapper::new(down))
+    }
+
+    /// Double and blur the image, one level up a Gaussian pyramid
+    pub fn pyr_up(&self) -> Result<MatWrapper> {
+        let (w, h) = self.size()?;
+        if w == 0 || h == 0 {
+            return Err(Error::InvalidParameter(
+                "Cannot upsample an empty image".to_string(),
+            ));
+        }
+        let mut up = Mat::default();
+        pyr_up(
+            self.as_mat(),
+            &mut up,
+            Size::new(w * 2, h * 2),
+            BORDER_DEFAULT,
+        )
+        .map_err(|e| Error::Platform(format!("Failed to upsample image: {}", e)))?;
+        Ok(MatWrapper::new(up))
+    }
+
+    /// Build up to `levels` successively halved images with `pyr_down`
+    ///
+    /// The first entry is half this image's size. Halving stops early
+    /// when a side would drop below `min_side` pixels, so the result may
+    /// be shorter than `levels`.
+    pub fn pyramid(&self, levels: usize, min_side: i32) -> Result<Vec<MatWrapper>> {
+        let mut pyramid: Vec<MatWrapper> = Vec::with_capacity(levels);
+        for _ in 0..levels {
+            let current = pyramid.last().unwrap_or(self);
+            let (w, h) = current.size()?;
+            if (w + 1) / 2 < min_side || (h + 1) / 2 < min_side {
+                break;
+            }
+            let down = current.pyr_down()?;
+            pyramid.push(down);
+        }
+        Ok(pyramid)
+    }
+}
+
+/// Resize an image by a uniform scale factor
+///
+/// Shrinking uses area interpolation, which avoids aliasing on UI artwork;
+/// enlarging uses bilinear interpolation. The result is at least 1x1.
+pub fn resize_by_factor(image: &MatWrapper, factor: f64) -> Result<MatWrapper> {
+    image.scale(factor, Interpolation::for_factor(factor))
+}
+
+/// Scale one side, rounding to the nearest pixel but never below 1
+fn scaled(side: i32, factor: f64) -> i32 {
+    ((side as f64 * factor).round() as i32).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::noise_mat;
+    use opencv::core::{Scalar, CV_8UC1};
+
+    /// Single channel image from rows of pixel values
+    fn gray(rows: &[&[u8]]) -> MatWrapper {
+        let (h, w) = (rows.len() as i32, rows[0].len() as i32);
+        let mut mat = Mat::new_rows_cols_with_default(h, w, CV_8UC1, Scalar::all(0.0)).unwrap();
+        mat.data_bytes_mut()
+            .unwrap()
+            .copy_from_slice(&rows.concat());
+        MatWrapper::new(mat)
+    }
+
+    fn flat(width: i32, height: i32, value: u8) -> MatWrapper {
+        let mat =
+            Mat::new_rows_cols_with_default(height, width, CV_8UC1, Scalar::all(value.into()))
+                .unwrap();
+        MatWrapper::new(mat)
+    }
+
+    fn pixels(image: &MatWrapper) -> Vec<u8> {
+        image.as_mat().data_bytes().unwrap().to_vec()
+    }
+
+    #[test]
+    fn test_resize_by_factor() {
+        let image = MatWrapper::new(noise_mat(40, 20, 1));
+
+        let larger = resize_by_factor(&image, 1.25).unwrap();
+        assert_eq!(larger.size().unwrap(), (50, 25));
+
+        let smaller = resize_by_factor(&image, 0.5).unwrap();
+        assert_eq!(smaller.size().unwrap(), (20, 10));
+        assert_eq!(smaller.channels().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_resize_never_empty() {
+        let image = MatWrapper::new(noise_mat(3, 3, 1));
+        let tiny = resize_by_factor(&image, 0.01).unwrap();
+        assert_eq!(tiny.size().unwrap(), (1, 1));
+    }
+
+    #[test]
+    fn test_resize_invalid_factor() {
+        let image = MatWrapper::new(noise_mat(3, 3, 1));
+        assert!(matches!(
+            resize_by_factor(&image, 0.0),
+            Err(Error::InvalidParameter(_))
+        ));
+        assert!(resize_by_factor(&image, f64::NAN).is_err());
+        assert!(matches!(
+            image.resize(0, 3, Interpolation::Linear),
+            Err(Error::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            MatWrapper::empty()
+                .unwrap()
+                .scale(2.0, Interpolation::Linear),
+            Err(Error::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn test_golden_nearest_enlarge() {
+        let image = gray(&[&[0, 100], &[200, 255]]);
+        let enlarged = image.scale(2.0, Interpolation::Nearest).unwrap();
+        assert_eq!(
+            pixels(&enlarged),
+            [
+                0, 0, 100, 100, //
+                0, 0, 100, 100, //
+                200, 200, 255, 255, //
+                200, 200, 255, 255,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_golden_linear_enlarge() {
+        // Pixel centers sit at half-pixel offsets; the borders replicate
+        let image = gray(&[&[0, 100]]);
+        let enlarged = image.resize(4, 1, Interpolation::Linear).unwrap();
+        assert_eq!(pixels(&enlarged), [0, 25, 75, 100]);
+    }
+
+    #[test]
+    fn test_golden_area_shrink() {
+        let image = gray(&[
+            &[0, 2, 10, 10],
+            &[4, 6, 10, 10],
+            &[100, 100, 50, 51],
+            &[100, 100, 52, 55],
+        ]);
+        let shrunk = image.scale(0.5, Interpolation::Area).unwrap();
+        assert_eq!(pixels(&shrunk), [3, 10, 100, 52]);
+    }
+
+    #[test]
+    fn test_golden_cubic_keeps_flat_areas() {
+        let image = flat(5, 4, 77);
+        let resized = image.resize(9, 7, Interpolation::Cubic).unwrap();
+        assert_eq!(pixels(&resized), vec![77; 63]);
+    }
+
+    #[test]
+    fn test_fit_within_keeps_aspect_ratio() {
+        let image = MatWrapper::new(noise_mat(40, 20, 1));
+        let fitted = image.fit_within(30, 30, Interpolation::Area).unwrap();
+        assert_eq!(fitted.size().unwrap(), (30, 15));
+        let fitted = image.fit_within(100, 25, Interpolation::Cubic).unwrap();
+        assert_eq!(fitted.size().unwrap(), (50, 25));
+    }
+
+    #[test]
+    fn test_pyramid() {
+        let image = MatWrapper::new(noise_mat(40, 21, 2));
+        let down = image.pyr_down().unwrap();
+        assert_eq!(down.size().unwrap(), (20, 11));
+        assert_eq!(down.pyr_up().unwrap().size().unwrap(), (40, 22));
+
+        let levels = image.pyramid(5, 4).unwrap();
+        let sizes: Vec<_> = levels.iter().map(|l| l.size().unwrap()).collect();
+        assert_eq!(sizes, [(20, 11), (10, 6)]);
+
+        let down = flat(8, 8, 42).pyr_down().unwrap();
+        assert_eq!(pixels(&down), vec![42; 16]);
+    }
+}
@@ -0,0 +1,260 @@
+//! Templates prepared for matching: the pattern image plus an optional mask
+
+use crate::image_cache::ImageCache;
+use crate::mat_wrapper::MatWrapper;
+use crate::resize::resize_by_factor;
+use opencv::core::{count_non_zero, extract_channel, Mat, CV_8UC1};
+use opencv::prelude::*;
+use sikulix_core::{Error, ImagePath, Pattern, PatternMask, Result};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::debug;
+
+/// A template image ready to be matched, optionally with a mask
+///
+/// Mask pixels that are zero are ignored when scoring a position; all other
+/// template pixels are compared as usual. The mask is a single channel
+/// CV_8U image of the same size as the template.
+///
+/// Templates loaded from files share their pixels with the [`ImageCache`],
+/// so loading a pattern again does not copy them.
+#[derive(Debug)]
+pub struct Template {
+    /// The template pixels
+    image: Arc<MatWrapper>,
+
+    /// Which template pixels take part in the score
+    mask: Option<Arc<MatWrapper>>,
+
+    /// File the pixels were loaded from, whose scaled copies are cached
+    source: Option<PathBuf>,
+}
+
+impl Template {
+    /// Create an unmasked template
+    pub fn new(image: MatWrapper) -> Self {
+        Self {
+            image: Arc::new(image),
+            mask: None,
+            source: None,
+        }
+    }
+
+    /// Create a template whose score only considers the nonzero `mask` pixels
+    ///
+    /// # Errors
+    /// Returns `Error::InvalidPattern` if the mask is not a single channel
+    /// 8-bit image of the template's size, or masks out every pixel.
+    pub fn with_mask(image: MatWrapper, mask: MatWrapper) -> Result<Self> {
+        Self::masked(Arc::new(image), Arc::new(mask), None)
+    }
+
+    fn masked(
+        image: Arc<MatWrapper>,
+        mask: Arc<MatWrapper>,
+        source: Option<PathBuf>,
+    ) -> Result<Self> {
+        let size = image.size()?;
+        let mask_size = mask.size()?;
+        if mask_size != size {
+            return Err(Error::InvalidPattern(format!(
+                "Mask is {}x{} but the pattern image is {}x{}",
+                mask_size.0, mask_size.1, size.0, size.1
+            )));
+        }
+        if mask.mat_type()? != CV_8UC1 {
+            return Err(Error::InvalidPattern(
+                "Mask must be a single channel 8-bit image".to_string(),
+            ));
+        }
+        if count_non_zero(mask.as_mat())? == 0 {
+            return Err(Error::InvalidPattern(
+                "Mask hides every pixel of the pattern".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            image,
+            mask: Some(mask),
+            source,
+        })
+    }
+
+    /// Load the image of `pattern`, and its mask if it defines one
+    ///
+    /// Relative image names are looked up in the global [`ImagePath`], and
+    /// decoded files are kept in the global [`ImageCache`].
+    ///
+    /// With [`PatternMask::Alpha`] the image is loaded with its alpha
+    /// channel, which becomes the mask; fully transparent pixels are ignored.
+    /// With [`PatternMask::Image`] the mask is loaded as grayscale and black
+    /// pixels are ignored.
+    pub fn load(pattern: &Pattern) -> Result<Self> {
+        let path = ImagePath::global().resolve(pattern.image.path_buf())?;
+        let cache = ImageCache::global();
+        match &pattern.mask {
+            None => Ok(Self {
+                image: cache.load(&path, true)?,
+                mask: None,
+                source: Some(path),
+            }),
+            Some(PatternMask::Alpha) => {
+                let image = cache.load(&path, false)?;
+                if image.channels()? != 4 {
+                    return Err(Error::InvalidPattern(format!(
+                        "{} has no alpha channel to use as mask",
+                        path.display()
+                    )));
+                }
+
+                let mut alpha = Mat::default();
+                extract_channel(image.as_mat(), &mut alpha, 3).map_err(|e| {
+                    Error::Platform(format!("Failed to extract alpha channel: {}", e))
+                })?;
+                debug!("Using alpha channel of {} as mask", path.display());
+                Self::masked(image, Arc::new(MatWrapper::new(alpha)), Some(path))
+            }
+            Some(PatternMask::Image(mask)) => {
+                let image = cache.load(&path, true)?;
+                let mask = ImagePath::global().resolve(mask.path_buf())?;
+                let mask = cache.load_grayscale(mask)?;
+                Self::masked(image, mask, Some(path))
+            }
+        }
+    }
+
+    /// Get the template pixels
+    pub fn image(&self) -> &MatWrapper {
+        &self.image
+    }
+
+    /// Get the mask, if the template has one
+    pub fn mask(&self) -> Option<&MatWrapper> {
+        self.mask.as_deref()
+    }
+
+    /// Get the template dimensions as (width, height)
+    pub fn size(&self) -> Result<(i32, i32)> {
+        Ok(self.image.size()?)
+    }
+
+    /// Copy the template and its mask (expensive operation)
+    pub fn try_clone(&self) -> Result<Self> {
+        let mask = match &self.mask {
+            Some(mask) => Some(Arc::new(mask.clone_mat()?)),
+            None => None,
+        };
+        Ok(Self {
+            image: Arc::new(self.image.clone_mat()?),
+            mask,
+            source: self.source.clone(),
+        })
+    }
+
+    /// Resize the template and its mask by a uniform scale factor
+    ///
+    /// Templates loaded from a file take the scaled image from the
+    /// [`ImageCache`], so each factor is resized only once.
+    pub fn scaled(&self, factor: f64) -> Result<Self> {
+        let image = match &self.source {
+            Some(path) => ImageCache::global().load_scaled(path, factor)?,
+            None => Arc::new(resize_by_factor(&self.image, factor)?),
+        };
+        let mask = match &self.mask {
+            Some(mask) => Some(Arc::new(resize_by_factor(mask, factor)?)),
+            None => None,
+        };
+        Ok(Self {
+            image,
+            mask,
+            source: None,
+        })
+    }
+}
+
+impl From<MatWrapper> for Template {
+    fn from(image: MatWrapper) -> Self {
+        Self::new(image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{noise_mat, png_fixture};
+    use opencv::core::{no_array, Rect, Scalar, CV_8UC4};
+    use sikulix_core::Image;
+    use tempfile::TempDir;
+
+    fn pattern_for(path: &std::path::Path) -> Pattern {
+        Pattern::new(Image::from_path(path.to_str().unwrap()))
+    }
+
+    #[test]
+    fn test_load_alpha_mask() {
+        let dir = TempDir::new().unwrap();
+        let mut icon = Mat::new_rows_cols_with_default(10, 12, CV_8UC4, Scalar::all(0.0)).unwrap();
+        {
+            let mut opaque = icon.roi_mut(Rect::new(2, 2, 4, 5)).unwrap();
+            opaque.set_to(&Scalar::all(255.0), &no_array()).unwrap();
+        }
+        let (path, _) = png_fixture(&dir, "icon.png", &icon);
+
+        let template = Template::load(&pattern_for(&path).mask_from_alpha()).unwrap();
+        assert_eq!(template.size().unwrap(), (12, 10));
+        let mask = template.mask().expect("alpha mask");
+        assert_eq!(count_non_zero(mask.as_mat()).unwrap(), 20);
+
+        let scaled = template.scaled(2.0).unwrap();
+        assert_eq!(scaled.mask().unwrap().size().unwrap(), (24, 20));
+
+        let copy = template.try_clone().unwrap();
+        assert_eq!(count_non_zero(copy.mask().unwrap().as_mat()).unwrap(), 20);
+    }
+
+    #[test]
+    fn test_load_shares_cached_pixels() {
+        let dir = TempDir::new().unwrap();
+        let (path, _) = png_fixture(&dir, "shared.png", &noise_mat(20, 10, 3));
+        let pattern = pattern_for(&path);
+
+        let first = Template::load(&pattern).unwrap();
+        let second = Template::load(&pattern).unwrap();
+        assert!(std::ptr::eq(first.image(), second.image()));
+
+        let half = first.scaled(0.5).unwrap();
+        assert_eq!(half.size().unwrap(), (10, 5));
+        assert!(std::ptr::eq(
+            half.image(),
+            second.scaled(0.5).unwrap().image()
+        ));
+
+        // Copies and templates made in memory do not touch the cache
+        let copy = first.try_clone().unwrap();
+        assert!(!std::ptr::eq(first.image(), copy.image()));
+        let unloaded = Template::new(copy.image().clone_mat().unwrap());
+        assert_eq!(unloaded.scaled(2.0).unwrap().size().unwrap(), (40, 20));
+    }
+
+    #[test]
+    fn test_alpha_mask_requires_alpha_channel() {
+        let dir = TempDir::new().unwrap();
+        let (path, _) = png_fixture(&dir, "opaque.png", &noise_mat(8, 8, 1));
+
+        let result = Template::load(&pattern_for(&path).mask_from_alpha());
+        assert!(matches!(result.unwrap_err(), Error::InvalidPattern(_)));
+    }
+
+    #[test]
+    fn test_mask_image_must_match_size() {
+        let dir = TempDir::new().unwrap();
+        let (path, _) = png_fixture(&dir, "icon.png", &noise_mat(8, 8, 1));
+        let (mask_path, _) = png_fixture(&dir, "mask.png", &noise_mat(6, 8, 2));
+
+        let pattern = pattern_for(&path).mask(Image::from_path(mask_path.to_str().unwrap()));
+        assert!(matches!(
+            Template::load(&pattern).unwrap_err(),
+            Error::InvalidPattern(_)
+        ));
+    }
+}
@@ -0,0 +1,35 @@
+//! Synthetic image fixtures shared by the unit tests
+
+use crate::image_loader::ImageLoader;
+use crate::mat_wrapper::MatWrapper;
+use opencv::core::{Mat, Vec3b, Vector, CV_8UC3};
+use opencv::imgcodecs::imencode;
+use opencv::prelude::*;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+/// Deterministic BGR noise image, so that every crop of it is unique
+pub fn noise_mat(width: i32, height: i32, seed: u32) -> Mat {
+    let mut mat =
+        Mat::new_rows_cols_with_default(height, width, CV_8UC3, (0, 0, 0, 0).into()).unwrap();
+    let mut state = seed;
+    for y in 0..height {
+        for x in 0..width {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            let v = (state >> 8).to_le_bytes();
+            *mat.at_2d_mut::<Vec3b>(y, x).unwrap() = Vec3b::from([v[0], v[1], v[2]]);
+        }
+    }
+    mat
+}
+
+/// Write a Mat as PNG and load it back through `ImageLoader`
+pub fn png_fixture(dir: &TempDir, name: &str, mat: &Mat) -> (PathBuf, MatWrapper) {
+    let path = dir.path().join(name);
+    let mut buf = Vector::<u8>::new();
+    imencode(".png", mat, &mut buf, &Vector::new()).unwrap();
+    fs::write(&path, buf.to_vec()).unwrap();
+    let loaded = ImageLoader::load_from_file(&path, true).unwrap();
+    (path, loaded)
+}
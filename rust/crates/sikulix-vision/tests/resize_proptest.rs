@@ -0,0 +1,125 @@
+//! Property-based tests for resizing and pyramids
+
+use opencv::core::{Mat, CV_8UC1, CV_8UC3};
+use opencv::prelude::*;
+use proptest::prelude::*;
+use sikulix_vision::{Interpolation, MatWrapper};
+
+/// Generate valid image dimensions
+fn image_dimensions() -> impl Strategy<Value = (i32, i32)> {
+    (1i32..=300, 1i32..=300)
+}
+
+/// Generate every interpolation mode
+fn interpolation() -> impl Strategy<Value = Interpolation> {
+    prop_oneof![
+        Just(Interpolation::Nearest),
+        Just(Interpolation::Linear),
+        Just(Interpolation::Cubic),
+        Just(Interpolation::Area),
+    ]
+}
+
+/// Uniformly colored BGR image
+fn flat_bgr(width: i32, height: i32, (b, g, r): (u8, u8, u8)) -> MatWrapper {
+    let mat = Mat::new_rows_cols_with_default(height, width, CV_8UC3, (b, g, r, 0).into()).unwrap();
+    MatWrapper::new(mat)
+}
+
+proptest! {
+    /// Property: Resizing produces exactly the requested size and keeps channels
+    #[test]
+    fn prop_resize_exact_size(
+        (width, height) in image_dimensions(),
+        (target_w, target_h) in image_dimensions(),
+        mode in interpolation(),
+    ) {
+        let image = flat_bgr(width, height, (10, 20, 30));
+        let resized = image.resize(target_w, target_h, mode).unwrap();
+
+        prop_assert_eq!(resized.size().unwrap(), (target_w, target_h));
+        prop_assert_eq!(resized.mat_type().unwrap(), CV_8UC3);
+    }
+
+    /// Property: Scaling rounds each side and never produces an empty image
+    #[test]
+    fn prop_scale_rounds_sides(
+        (width, height) in image_dimensions(),
+        factor in 0.01f64..4.0,
+        mode in interpolation(),
+    ) {
+        let image = flat_bgr(width, height, (0, 0, 0));
+        let (w, h) = image.scale(factor, mode).unwrap().size().unwrap();
+
+        let expected = |side: i32| ((side as f64 * factor).round() as i32).max(1);
+        prop_assert_eq!((w, h), (expected(width), expected(height)));
+    }
+
+    /// Property: Fitting stays inside the box, touches it, and keeps the
+    /// aspect ratio up to rounding
+    #[test]
+    fn prop_fit_within_keeps_aspect_ratio(
+        (width, height) in image_dimensions(),
+        (max_w, max_h) in image_dimensions(),
+    ) {
+        let image = flat_bgr(width, height, (0, 0, 0));
+        let (w, h) = image.fit_within(max_w, max_h, Interpolation::Area).unwrap().size().unwrap();
+
+        prop_assert!(w <= max_w && h <= max_h, "{}x{} exceeds {}x{}", w, h, max_w, max_h);
+        prop_assert!(w == max_w || h == max_h, "{}x{} does not touch {}x{}", w, h, max_w, max_h);
+
+        // Each side is off by at most half a pixel, unless it was raised to 1
+        let factor = (max_w as f64 / width as f64).min(max_h as f64 / height as f64);
+        if width as f64 * factor >= 0.5 && height as f64 * factor >= 0.5 {
+            let skew = (w as i64 * height as i64 - h as i64 * width as i64).abs();
+            prop_assert!(2 * skew <= (width + height) as i64,
+                "{}x{} from {}x{} skews the aspect ratio", w, h, width, height);
+        }
+    }
+
+    /// Property: A flat image stays flat under every interpolation, up to
+    /// the rounding of fixed-point filter weights
+    #[test]
+    fn prop_flat_image_stays_flat(
+        (width, height) in image_dimensions(),
+        (target_w, target_h) in image_dimensions(),
+        mode in interpolation(),
+        color in (any::<u8>(), any::<u8>(), any::<u8>()),
+    ) {
+        let image = flat_bgr(width, height, color);
+        let resized = image.resize(target_w, target_h, mode).unwrap();
+
+        let (b, g, r) = color;
+        for pixel in resized.as_mat().data_bytes().unwrap().chunks_exact(3) {
+            for (&got, want) in pixel.iter().zip([b, g, r]) {
+                prop_assert!(got.abs_diff(want) <= 1, "{:?} is not {:?}", pixel, color);
+            }
+        }
+    }
+
+    /// Property: Pyramid levels halve each side, rounding up, and return
+    /// to twice their size
+    #[test]
+    fn prop_pyramid_sizes(
+        (width, height) in image_dimensions(),
+        levels in 0usize..6,
+        min_side in 1i32..16,
+    ) {
+        let mat = Mat::new_rows_cols_with_default(height, width, CV_8UC1, 99.into()).unwrap();
+        let image = MatWrapper::new(mat);
+        let pyramid = image.pyramid(levels, min_side).unwrap();
+        prop_assert!(pyramid.len() <= levels);
+
+        let (mut w, mut h) = (width, height);
+        for level in &pyramid {
+            let (lw, lh) = level.size().unwrap();
+            prop_assert_eq!((lw, lh), ((w + 1) / 2, (h + 1) / 2));
+            prop_assert!(lw >= min_side && lh >= min_side);
+            prop_assert_eq!(level.pyr_up().unwrap().size().unwrap(), (lw * 2, lh * 2));
+            (w, h) = (lw, lh);
+        }
+        if pyramid.len() < levels {
+            prop_assert!((w + 1) / 2 < min_side || (h + 1) / 2 < min_side);
+        }
+    }
+}
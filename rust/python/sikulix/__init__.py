@@ -18,13 +18,23 @@ Basic Usage:
     screen.type("username")
 """
 
-from sikulix._native import Location, Offset, Region
+from sikulix._native import (
+    FindFailed,
+    ImagePath,
+    Location,
+    Offset,
+    Region,
+    VirtualDesktop,
+)
 
 __version__ = "3.0.0"
 __all__ = [
+    "FindFailed",
+    "ImagePath",
     "Location",
     "Offset",
     "Region",
+    "VirtualDesktop",
     # Screen, Match, Pattern, Image will be added in later phases
 ]
 
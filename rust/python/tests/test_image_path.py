@@ -0,0 +1,48 @@
+"""Tests for the ImagePath registry."""
+
+import pytest
+from sikulix import ImagePath
+
+
+@pytest.fixture(autouse=True)
+def clean_image_path():
+    """Start and end every test with an empty ImagePath."""
+    ImagePath.reset()
+    yield
+    ImagePath.reset()
+
+
+class TestImagePath:
+    """Test ImagePath class."""
+
+    def test_add_and_remove(self, tmp_path):
+        """Test managing the searched directories."""
+        ImagePath.add(tmp_path / "a")
+        ImagePath.add(tmp_path / "b")
+        ImagePath.add(tmp_path / "a")
+        assert ImagePath.get_paths() == [tmp_path / "a", tmp_path / "b"]
+
+        assert ImagePath.remove(tmp_path / "a")
+        assert not ImagePath.remove(tmp_path / "a")
+        assert ImagePath.get_paths() == [tmp_path / "b"]
+
+    def test_resolve_without_extension(self, tmp_path):
+        """Test resolving a name in the bundle path."""
+        (tmp_path / "button.png").write_bytes(b"")
+        ImagePath.set_bundle_path(tmp_path)
+        assert ImagePath.get_bundle_path() == tmp_path
+        assert ImagePath.resolve("button") == tmp_path / "button.png"
+
+    def test_resolve_sikuli_folder(self, tmp_path):
+        """Test falling back to a .sikuli bundle folder."""
+        (tmp_path / "login.sikuli").mkdir()
+        (tmp_path / "login.sikuli" / "user.png").write_bytes(b"")
+        ImagePath.add(tmp_path / "login")
+        assert ImagePath.resolve("user.png") == tmp_path / "login.sikuli" / "user.png"
+
+    def test_missing_image(self, tmp_path):
+        """Test that the error lists the searched locations."""
+        ImagePath.set_bundle_path(tmp_path)
+        with pytest.raises(FileNotFoundError) as error:
+            ImagePath.resolve("missing")
+        assert str(tmp_path / "missing.png") in str(error.value)
@@ -0,0 +1,68 @@
+"""Tests for the VirtualDesktop test backend."""
+
+import pytest
+from sikulix import Location, Region, VirtualDesktop
+
+
+class TestVirtualDesktop:
+    """Test VirtualDesktop class."""
+
+    def test_fill_and_visibility(self):
+        """Test adding, hiding and showing elements."""
+        desktop = VirtualDesktop(200, 100)
+        region = desktop.fill("dialog", Region(10, 10, 50, 30), (255, 255, 255))
+        assert (region.x, region.y, region.w, region.h) == (10, 10, 50, 30)
+        assert desktop.is_visible("dialog")
+
+        desktop.hide("dialog")
+        assert not desktop.is_visible("dialog")
+        desktop.show("dialog")
+        assert desktop.is_visible("dialog")
+
+    def test_unknown_element(self):
+        """Test showing an element that was never added."""
+        desktop = VirtualDesktop(200, 100)
+        with pytest.raises(ValueError):
+            desktop.show("missing")
+
+    def test_click_reaction(self):
+        """Test that clicking a button swaps the scene."""
+        desktop = VirtualDesktop(200, 100)
+        desktop.fill("ok", Region(20, 20, 30, 10), (0, 255, 0))
+        desktop.fill("done", Region(20, 20, 30, 10), (0, 0, 255), visible=False)
+        desktop.on_click(Region(20, 20, 30, 10), show=["done"], hide=["ok"])
+
+        desktop.click(Location(5, 5))
+        assert desktop.is_visible("ok")
+
+        desktop.click(Location(25, 25))
+        assert not desktop.is_visible("ok")
+        assert desktop.is_visible("done")
+
+    def test_type_reaction(self):
+        """Test that typing the expected text hides a prompt."""
+        desktop = VirtualDesktop(200, 100)
+        desktop.fill("prompt", Region(0, 0, 10, 10), (255, 0, 0))
+        desktop.on_type("secret\n", hide=["prompt"])
+
+        desktop.type("wrong\n")
+        assert desktop.is_visible("prompt")
+        desktop.type("secret\n")
+        assert not desktop.is_visible("prompt")
+
+    def test_records_events(self):
+        """Test that input events are recorded in order."""
+        desktop = VirtualDesktop(200, 100)
+        desktop.click(Location(3, 4))
+        desktop.type("hi")
+
+        assert desktop.events() == [
+            ("move", 3, 4),
+            ("down", "left", 3, 4),
+            ("up", "left", 3, 4),
+            ("type", "hi"),
+        ]
+        assert desktop.pointer().x == 3
+
+        desktop.clear_events()
+        assert desktop.events() == []